@@ -0,0 +1,386 @@
+//! Derive macro for `elm_rust_binding::ElmType`. Use it via the `derive` feature of `elm-rust-binding`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Error, Fields, LitStr,
+    Result,
+};
+
+/// Derive an explicit Elm type mapping including Json codecs matching the serde representation.
+///
+/// Supported attributes:
+/// - `#[elm(module = "Api.User")]` on the type: the Elm module defining the corresponding Elm type.
+///   Required for enums, which are mapped to Elm custom types. Structs without a module are mapped structurally
+///   (records, tuples or the wrapped type for newtypes).
+/// - `#[elm(name = "User")]` on the type or a variant: the name of the Elm type or constructor (defaults to the Rust name).
+/// - `#[elm(name = "userName")]` on a field: the name of the Elm record field (defaults to the Rust name).
+///   Names which are not valid Elm record fields (e.g. `r#type` or `_id`) are rejected.
+/// - `#[serde(rename = "...")]` and `#[serde(rename_all = "...")]` are respected for the Json representation.
+///   Other serde attributes changing the representation (e.g. `skip`, `with` or `untagged`) are rejected.
+#[proc_macro_derive(ElmType, attributes(elm))]
+pub fn derive_elm_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let private = private();
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "ElmType cannot be derived for generic types",
+        ));
+    }
+    let elm_attributes = ElmAttributes::parse(&input.attrs)?;
+    let serde_attributes = SerdeAttributes::parse(&input.attrs)?;
+
+    let ident = &input.ident;
+    let elm_name = elm_attributes.name.unwrap_or_else(|| ident.to_string());
+    let elm_module = match &elm_attributes.module {
+        Some(module) => quote! { ::std::option::Option::Some(#module) },
+        None => quote! { ::std::option::Option::None },
+    };
+
+    let shape = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(_) => {
+                let fields = expand_fields(&data.fields, &serde_attributes)?;
+                quote! { #private::Shape::Record(::std::vec![#(#fields),*]) }
+            }
+            Fields::Unnamed(unnamed) => {
+                if elm_attributes.module.is_none() && unnamed.unnamed.len() > 3 {
+                    return Err(Error::new(
+                        unnamed.span(),
+                        "Elm tuples have at most 3 elements, specify #[elm(module = \"...\")] to map to a custom type instead",
+                    ));
+                }
+                let elements = expand_elements(&data.fields)?;
+                quote! { #private::Shape::Tuple(::std::vec![#(#elements),*]) }
+            }
+            Fields::Unit => quote! { #private::Shape::Unit },
+        },
+        Data::Enum(data) => {
+            if elm_attributes.module.is_none() {
+                return Err(Error::new(
+                    ident.span(),
+                    "Enums are mapped to Elm custom types, which need #[elm(module = \"...\")]",
+                ));
+            }
+            let variants = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let elm_attributes = ElmAttributes::parse(&variant.attrs)?;
+                    let variant_serde_attributes = SerdeAttributes::parse(&variant.attrs)?;
+                    let rust_name = variant.ident.to_string();
+                    let elm_name = elm_attributes.name.unwrap_or_else(|| rust_name.clone());
+                    let json_tag = variant_serde_attributes
+                        .rename
+                        .clone()
+                        .unwrap_or_else(|| serde_attributes.rename_variant(&rust_name));
+                    let shape = match &variant.fields {
+                        Fields::Named(_) => {
+                            let fields = expand_fields(&variant.fields, &variant_serde_attributes)?;
+                            quote! { #private::VariantShape::Record(::std::vec![#(#fields),*]) }
+                        }
+                        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                            let elements = expand_elements(&variant.fields)?;
+                            quote! { #private::VariantShape::Newtype(#(#elements)*) }
+                        }
+                        Fields::Unnamed(_) => {
+                            let elements = expand_elements(&variant.fields)?;
+                            quote! { #private::VariantShape::Tuple(::std::vec![#(#elements),*]) }
+                        }
+                        Fields::Unit => quote! { #private::VariantShape::Unit },
+                    };
+                    Ok(quote! {
+                        #private::Variant { elm_name: #elm_name, json_tag: #json_tag, shape: #shape }
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            quote! { #private::Shape::CustomType(::std::vec![#(#variants),*]) }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                ident.span(),
+                "ElmType cannot be derived for unions",
+            ))
+        }
+    };
+
+    let ident_string = ident.to_string();
+    Ok(quote! {
+        const _: () = {
+            fn shape() -> #private::Shape {
+                #shape
+            }
+
+            const DEFINITION: #private::Definition = #private::Definition {
+                // The location is included, since types local to functions may share their module and name
+                key: ::std::concat!(
+                    ::std::module_path!(), "::", #ident_string, "_", ::std::line!(), "_", ::std::column!()
+                ),
+                elm_module: #elm_module,
+                elm_name: #elm_name,
+                shape,
+            };
+
+            impl ::elm_rust_binding::ElmType for #ident {
+                fn elm_type() -> ::std::string::String {
+                    DEFINITION.elm_type()
+                }
+
+                fn elm_decoder() -> ::std::string::String {
+                    DEFINITION.elm_decoder()
+                }
+
+                fn elm_encoder() -> ::std::string::String {
+                    DEFINITION.elm_encoder()
+                }
+
+                fn elm_dependencies(dependencies: &mut ::elm_rust_binding::ElmDependencies) {
+                    DEFINITION.elm_dependencies(dependencies)
+                }
+            }
+
+            #[#private::linkme::distributed_slice(#private::ELM_TYPES)]
+            #[linkme(crate = #private::linkme)]
+            static REGISTRATION: #private::Registration =
+                #private::Registration::of::<#ident>();
+        };
+    })
+}
+
+/// Path to the hidden helpers of `elm_rust_binding`. Everything is fully qualified,
+/// since the derived type or its field types might share a name with one of the helpers.
+fn private() -> TokenStream2 {
+    quote! { ::elm_rust_binding::__private }
+}
+
+fn expand_fields(fields: &Fields, serde_attributes: &SerdeAttributes) -> Result<Vec<TokenStream2>> {
+    let private = private();
+    fields
+        .iter()
+        .map(|field| {
+            let elm_attributes = ElmAttributes::parse(&field.attrs)?;
+            let field_serde_attributes = SerdeAttributes::parse(&field.attrs)?;
+            let rust_name = field
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string().trim_start_matches("r#").to_owned())
+                .unwrap_or_default();
+            let elm_name = elm_attributes.name.unwrap_or_else(|| rust_name.clone());
+            if !is_elm_field_name(&elm_name) {
+                return Err(Error::new(
+                    field.span(),
+                    format!("`{elm_name}` is not a valid Elm record field name, specify one via #[elm(name = \"...\")]"),
+                ));
+            }
+            let json_key = field_serde_attributes
+                .rename
+                .unwrap_or_else(|| serde_attributes.rename_field(&rust_name));
+            let ty = &field.ty;
+            Ok(quote! {
+                #private::Field { elm_name: #elm_name, json_key: #json_key, element: #private::Element::of::<#ty>() }
+            })
+        })
+        .collect()
+}
+
+/// Whether the name can be used as an Elm record field, i.e. it is a lowercase identifier but no keyword.
+fn is_elm_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(char::is_lowercase)
+        && chars.all(|char| char.is_alphanumeric() || char == '_')
+        && !ELM_RESERVED_WORDS.contains(&name)
+}
+
+const ELM_RESERVED_WORDS: &[&str] = &[
+    "if", "then", "else", "case", "of", "let", "in", "type", "module", "where", "import",
+    "exposing", "as", "port", "effect", "alias", "infix",
+];
+
+fn expand_elements(fields: &Fields) -> Result<Vec<TokenStream2>> {
+    let private = private();
+    fields
+        .iter()
+        .map(|field| {
+            SerdeAttributes::parse(&field.attrs)?;
+            let ty = &field.ty;
+            Ok(quote! { #private::Element::of::<#ty>() })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct ElmAttributes {
+    name: Option<String>,
+    module: Option<String>,
+}
+
+impl ElmAttributes {
+    fn parse(attributes: &[Attribute]) -> Result<Self> {
+        let mut result = Self::default();
+        for attribute in attributes {
+            if !attribute.path().is_ident("elm") {
+                continue;
+            }
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    result.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("module") {
+                    result.module = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("Unsupported elm attribute, expected `name` or `module`"))
+                }
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// The subset of serde attributes affecting the Json representation that we support.
+#[derive(Default)]
+struct SerdeAttributes {
+    rename: Option<String>,
+    rename_all: Option<String>,
+}
+
+impl SerdeAttributes {
+    fn parse(attributes: &[Attribute]) -> Result<Self> {
+        let mut result = Self::default();
+        for attribute in attributes {
+            if !attribute.path().is_ident("serde") {
+                continue;
+            }
+            attribute.parse_nested_meta(|meta| {
+                if (meta.path.is_ident("rename") || meta.path.is_ident("rename_all"))
+                    && meta.input.peek(syn::token::Paren)
+                {
+                    return Err(meta.error(
+                        "Different names for serializing and deserializing are not supported by ElmType",
+                    ));
+                } else if meta.path.is_ident("rename") {
+                    result.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    let rename_all = meta.value()?.parse::<LitStr>()?;
+                    if !RENAME_RULES.contains(&rename_all.value().as_str()) {
+                        return Err(Error::new(rename_all.span(), "Unknown rename rule"));
+                    }
+                    result.rename_all = Some(rename_all.value());
+                } else if UNSUPPORTED_SERDE_ATTRIBUTES
+                    .iter()
+                    .any(|unsupported| meta.path.is_ident(unsupported))
+                {
+                    return Err(meta.error(
+                        "This serde attribute changes the Json representation in a way ElmType does not support",
+                    ));
+                } else {
+                    // Ignore the remaining attributes (e.g. `default`, `bound` or `deny_unknown_fields`),
+                    // which only affect how strict deserializing is or the generated Rust code
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+
+    fn rename_field(&self, field: &str) -> String {
+        let words = field.split('_').filter(|word| !word.is_empty());
+        self.rename(field, words.map(str::to_owned).collect())
+    }
+
+    fn rename_variant(&self, variant: &str) -> String {
+        let mut words = Vec::<String>::new();
+        for char in variant.chars() {
+            match words.last_mut() {
+                Some(word) if !char.is_uppercase() => word.push(char),
+                _ => words.push(char.to_string()),
+            }
+        }
+        self.rename(variant, words)
+    }
+
+    fn rename(&self, original: &str, words: Vec<String>) -> String {
+        let lower = words.iter().map(|word| word.to_lowercase());
+        let capitalized = words.iter().map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| {
+                    first
+                        .to_uppercase()
+                        .chain(chars.flat_map(char::to_lowercase))
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+        match self.rename_all.as_deref() {
+            Some("lowercase") => original.to_lowercase(),
+            Some("UPPERCASE") => original.to_uppercase(),
+            Some("PascalCase") => capitalized.collect::<Vec<String>>().concat(),
+            Some("camelCase") => lower
+                .take(1)
+                .chain(capitalized.skip(1))
+                .collect::<Vec<_>>()
+                .concat(),
+            Some("snake_case") => lower.collect::<Vec<_>>().join("_"),
+            Some("SCREAMING_SNAKE_CASE") => lower.collect::<Vec<_>>().join("_").to_uppercase(),
+            Some("kebab-case") => lower.collect::<Vec<_>>().join("-"),
+            Some("SCREAMING-KEBAB-CASE") => lower.collect::<Vec<_>>().join("-").to_uppercase(),
+            _ => original.to_owned(),
+        }
+    }
+}
+
+fn skip_meta(meta: &syn::meta::ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta(&nested))?;
+    }
+    Ok(())
+}
+
+/// Serde attributes which change the Json representation (or which names are accepted while deserializing),
+/// so the codecs derived from the Rust type would not match it.
+const UNSUPPORTED_SERDE_ATTRIBUTES: &[&str] = &[
+    "tag",
+    "content",
+    "untagged",
+    "flatten",
+    "transparent",
+    "skip",
+    "skip_serializing",
+    "skip_deserializing",
+    "skip_serializing_if",
+    "with",
+    "serialize_with",
+    "deserialize_with",
+    "alias",
+    "from",
+    "try_from",
+    "into",
+    "rename_all_fields",
+    "other",
+    "variant_identifier",
+    "field_identifier",
+];
+
+const RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
@@ -0,0 +1,171 @@
+use std::{convert::Infallible, marker::PhantomData};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "quickjs")]
+use crate::quickjs::ProgramModule;
+#[cfg(feature = "v8")]
+use crate::v8::ProgramModule;
+use crate::{error::Result, ElmProgram};
+
+/// A handle to a compiled `Browser.application`, created via `ElmRoot::prepare_application`.
+/// Every call to `init` starts a new, independent instance of the application at the given url.
+///
+/// The instances are driven through their view, url and ports: messages can not be sent to them and
+/// their model is not passed to Rust (so `ElmProgram::send` and `ElmProgram::model` are not available),
+/// since the `Msg` of an application usually contains a `Url` and its model the `Browser.Navigation.Key`.
+pub struct ElmApplicationHandle<Flags> {
+    pub(crate) module: ProgramModule,
+    pub(crate) _type: PhantomData<Flags>,
+}
+
+impl<Flags> ElmApplicationHandle<Flags>
+where
+    Flags: Serialize,
+{
+    /// Starts an instance of the application at the (absolute) url, e.g. `https://example.com/users/1`,
+    /// by calling its `init` with the given flags.
+    #[cfg(feature = "v8")]
+    pub fn init(&self, url: &str, flags: Flags) -> Result<ElmProgram<'_, Infallible, Infallible>> {
+        let id = self
+            .module
+            .init_application(serde_json::to_value(flags)?, url)?;
+        Ok(ElmProgram {
+            module: &self.module,
+            id,
+            _type: PhantomData,
+        })
+    }
+
+    /// Starts an instance of the application at the (absolute) url, e.g. `https://example.com/users/1`,
+    /// by calling its `init` with the given flags.
+    #[cfg(feature = "quickjs")]
+    pub async fn init(
+        &self,
+        url: &str,
+        flags: Flags,
+    ) -> Result<ElmProgram<'_, Infallible, Infallible>> {
+        let id = self
+            .module
+            .init_application(serde_json::to_value(flags)?, url)
+            .await?;
+        Ok(ElmProgram {
+            module: &self.module,
+            id,
+            _type: PhantomData,
+        })
+    }
+}
+
+/// A navigation requested by the Elm code of an application via `Browser.Navigation`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Navigation {
+    /// `pushUrl`, with the url resolved against the previous location
+    PushUrl { url: String },
+    /// `replaceUrl`, with the url resolved against the previous location
+    ReplaceUrl { url: String },
+    /// `back` (negative steps) or `forward`
+    Go { steps: i32 },
+    /// `load`, which would leave the application in a browser and therefore keeps the location
+    Load { url: String },
+    /// `reload` or `reloadAndSkipCache`
+    Reload,
+}
+
+/// The location and history of programs created via `ElmRoot::prepare_application`.
+/// Links in the view are handled by `onUrlRequest` when they are clicked via `ElmProgram::click`.
+impl<Msg, Model> ElmProgram<'_, Msg, Model> {
+    /// The current url of the application.
+    #[cfg(feature = "v8")]
+    pub fn url(&self) -> Result<String> {
+        self.module.call("url", vec![self.id.into()])
+    }
+
+    /// The current url of the application.
+    #[cfg(feature = "quickjs")]
+    pub async fn url(&self) -> Result<String> {
+        self.module.call("url", vec![self.id.into()]).await
+    }
+
+    /// Returns the navigations the application requested since the last call, in the order they were requested.
+    #[cfg(feature = "v8")]
+    pub fn take_navigations(&self) -> Result<Vec<Navigation>> {
+        self.module.call("takeNavigations", vec![self.id.into()])
+    }
+
+    /// Returns the navigations the application requested since the last call, in the order they were requested.
+    #[cfg(feature = "quickjs")]
+    pub async fn take_navigations(&self) -> Result<Vec<Navigation>> {
+        self.module
+            .call("takeNavigations", vec![self.id.into()])
+            .await
+    }
+
+    /// Changes the url without the application asking for it (e.g. by entering it in the address bar of a browser),
+    /// which is passed to its `onUrlChange`. Relative urls are resolved against the current url.
+    #[cfg(feature = "v8")]
+    pub fn change_url(&self, url: &str) -> Result<()> {
+        self.module
+            .call::<()>("changeUrl", vec![self.id.into(), url.into()])?;
+        self.module.serve_http(self.id)
+    }
+
+    /// Changes the url without the application asking for it (e.g. by entering it in the address bar of a browser),
+    /// which is passed to its `onUrlChange`. Relative urls are resolved against the current url.
+    #[cfg(feature = "quickjs")]
+    pub async fn change_url(&self, url: &str) -> Result<()> {
+        self.module
+            .call::<()>("changeUrl", vec![self.id.into(), url.into()])
+            .await?;
+        self.module.serve_http(self.id).await
+    }
+
+    /// Goes back to the previous url like the back button of a browser, which is passed to `onUrlChange`.
+    #[cfg(feature = "v8")]
+    pub fn back(&self) -> Result<()> {
+        self.module.call::<()>("back", vec![self.id.into()])?;
+        self.module.serve_http(self.id)
+    }
+
+    /// Goes back to the previous url like the back button of a browser, which is passed to `onUrlChange`.
+    #[cfg(feature = "quickjs")]
+    pub async fn back(&self) -> Result<()> {
+        self.module.call::<()>("back", vec![self.id.into()]).await?;
+        self.module.serve_http(self.id).await
+    }
+}
+
+/// Prepends the `window`, `history` and `document.location` shims to the ESM compatible binding.
+pub(crate) fn with_navigation_shim(esm_compiled_binding: &str) -> String {
+    format!("{NAVIGATION_JS}{esm_compiled_binding}")
+}
+
+const NAVIGATION_JS: &str = include_str!("./templates/navigation.js");
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::Navigation;
+
+    #[test]
+    fn navigations_from_elm() {
+        let navigations: Vec<Navigation> = serde_json::from_value(json!([
+            { "kind": "pushUrl", "url": "http://localhost/users/1" },
+            { "kind": "go", "steps": -1 },
+            { "kind": "reload" }
+        ]))
+        .unwrap();
+        assert_eq!(
+            navigations,
+            [
+                Navigation::PushUrl {
+                    url: "http://localhost/users/1".to_owned()
+                },
+                Navigation::Go { steps: -1 },
+                Navigation::Reload
+            ]
+        );
+    }
+}
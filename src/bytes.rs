@@ -0,0 +1,113 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::{error::Result, json::FallibleOutput, ElmDependencies, ElmFunctionHandle, PortType};
+
+/// A handle to an Elm `Bytes.Decode.Decoder`, created via `ElmRoot::prepare_bytes_decoder`.
+/// Calling it runs `Bytes.Decode.decode` in Elm.
+pub struct ElmBytesDecoderHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<Vec<u8>, FallibleOutput>,
+    pub(crate) _type: PhantomData<O>,
+}
+
+impl<O> ElmBytesDecoderHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Decodes the given bytes with the Elm decoder.
+    /// Returns `None` if the Elm decoder fails.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, bytes: &[u8]) -> Result<Option<O>> {
+        let result = self.handle.call(bytes.to_vec())?;
+        bytes_decoder_output(result)
+    }
+
+    /// Decodes the given bytes with the Elm decoder.
+    /// Returns `None` if the Elm decoder fails.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, bytes: &[u8]) -> Result<Option<O>> {
+        let result = self.handle.call(bytes.to_vec()).await?;
+        bytes_decoder_output(result)
+    }
+}
+
+fn bytes_decoder_output<O: DeserializeOwned>(output: FallibleOutput) -> Result<Option<O>> {
+    match output.into_result::<String>()? {
+        Ok(value) => Ok(Some(serde_json::from_value(value)?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Wraps a bytes decoder into a function decoding a list of bytes into a `Result String a`.
+pub(crate) fn decode_bytes(decoder: &str) -> String {
+    format!(
+        "listToBytes >> Bytes.Decode.decode {decoder} >> Result.fromMaybe \"Bytes.Decode.decode failed\""
+    )
+}
+
+/// Wraps a function returning a bytes encoder into a function returning the list of encoded bytes.
+pub(crate) fn encode_bytes(encoder: &str) -> String {
+    format!("{encoder} >> Bytes.Encode.encode >> bytesToList")
+}
+
+/// Bytes are passed through the ports as lists of integers, which are converted with these declarations.
+pub(crate) fn bytes_dependencies(dependencies: &mut ElmDependencies) {
+    dependencies.import("Bytes");
+    dependencies.import("Bytes.Decode");
+    dependencies.import("Bytes.Encode");
+    if dependencies.declare("listToBytes") {
+        dependencies.define("listToBytes", LIST_TO_BYTES.to_owned());
+        dependencies.define("bytesToList", BYTES_TO_LIST.to_owned());
+    }
+}
+
+/// A single argument containing the bytes.
+pub(crate) fn bytes_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    bytes_dependencies(dependencies);
+    Ok(vec![PortType::Native("(List Int)".to_owned())])
+}
+
+const LIST_TO_BYTES: &str = "listToBytes : List Int -> Bytes.Bytes
+listToBytes =
+    List.map Bytes.Encode.unsignedInt8 >> Bytes.Encode.sequence >> Bytes.Encode.encode";
+
+const BYTES_TO_LIST: &str = "bytesToList : Bytes.Bytes -> List Int
+bytesToList bytes =
+    let
+        step ( remaining, values ) =
+            if remaining <= 0 then
+                Bytes.Decode.succeed (Bytes.Decode.Done (List.reverse values))
+
+            else
+                Bytes.Decode.map (\\value -> Bytes.Decode.Loop ( remaining - 1, value :: values )) Bytes.Decode.unsignedInt8
+    in
+    Bytes.Decode.decode (Bytes.Decode.loop ( Bytes.width bytes, [] ) step) bytes
+        |> Maybe.withDefault []";
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{bytes_decoder_output, bytes_input};
+    use crate::{json::FallibleOutput, ElmDependencies};
+
+    #[test]
+    fn failed_decoder() {
+        let output = bytes_decoder_output::<u8>(FallibleOutput::failed(json!("failed"))).unwrap();
+        assert_eq!(output, None);
+    }
+
+    #[test]
+    fn conversions_declared() {
+        let mut dependencies = ElmDependencies::default();
+        bytes_input(&mut dependencies).unwrap();
+        let declarations = dependencies.declarations();
+        assert!(declarations.contains("listToBytes : List Int -> Bytes.Bytes"));
+        assert!(declarations.contains("bytesToList : Bytes.Bytes -> List Int"));
+        assert_eq!(
+            dependencies.imports().collect::<Vec<_>>(),
+            ["Bytes", "Bytes.Decode", "Bytes.Encode"]
+        );
+    }
+}
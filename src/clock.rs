@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// The initial state of the virtual clock every binding call and program instance runs with.
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub(crate) struct ClockSettings {
+    /// Milliseconds since the Unix epoch
+    pub now: f64,
+    /// Seed of `Math.random`
+    pub seed: u32,
+}
+
+impl ClockSettings {
+    pub fn with_start_time(self, since_epoch: Duration) -> Self {
+        Self {
+            now: since_epoch.as_millis() as f64,
+            ..self
+        }
+    }
+}
+
+/// Prepends the virtual clock to the ESM compatible binding, so the compiled Elm code uses it instead of the real timers.
+pub(crate) fn with_virtual_clock(esm_compiled_binding: &str) -> String {
+    format!("{CLOCK_JS}{esm_compiled_binding}")
+}
+
+/// Converts a duration into the milliseconds of the virtual clock.
+pub(crate) fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+const CLOCK_JS: &str = include_str!("./templates/clock.js");
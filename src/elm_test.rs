@@ -0,0 +1,291 @@
+use std::{fmt::Display, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{error::Result, isolation::module_header, ElmDependencies, Error, PortType};
+
+/// Seed of the fuzz tests. Fixed, so test runs are reproducible.
+pub(crate) const SEED: i32 = 0;
+/// Number of runs of each fuzz test, same as the default of `elm-test`.
+pub(crate) const RUNS: i32 = 100;
+
+/// How a test was run, see `Test.Runner.SeededRunners`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElmTestMode {
+    Plain,
+    /// The test (or another test of the same top-level `Test`) uses `Test.only`.
+    Only,
+    /// The test (or another test of the same top-level `Test`) uses `Test.skip`.
+    Skipping,
+    /// The top-level `Test` is invalid, e.g. because of duplicate descriptions.
+    Invalid,
+}
+
+/// The result of a single Elm test.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ElmTestResult {
+    /// The fully qualified name of the top-level `Test`, followed by the descriptions of the test
+    pub labels: Vec<String>,
+    /// The formatted failures, empty if the test passed
+    pub failures: Vec<String>,
+    pub mode: ElmTestMode,
+}
+
+impl ElmTestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// The results of all tests run via `ElmRoot::run_elm_tests`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElmTestReport {
+    pub results: Vec<ElmTestResult>,
+}
+
+impl ElmTestReport {
+    /// Whether all tests passed. Like `elm-test`, runs without any tests
+    /// or using `Test.only` or `Test.skip` do not count as passed.
+    pub fn passed(&self) -> bool {
+        !self.results.is_empty()
+            && self
+                .results
+                .iter()
+                .all(|result| result.passed() && result.mode == ElmTestMode::Plain)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ElmTestResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+
+    /// Panics with the formatted report if not all tests passed, for use in Rust tests.
+    pub fn assert_passed(&self) {
+        assert!(self.passed(), "{self}");
+    }
+}
+
+impl Display for ElmTestReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for result in self.failures() {
+            writeln!(f, "✗ {}", result.labels.join(" > "))?;
+            for failure in &result.failures {
+                writeln!(f, "{failure}\n")?;
+            }
+        }
+        let modes = [
+            (ElmTestMode::Only, "Test.only"),
+            (ElmTestMode::Skipping, "Test.skip"),
+        ];
+        for (mode, function) in modes {
+            if self.results.iter().any(|result| result.mode == mode) {
+                writeln!(f, "{function} was used")?;
+            }
+        }
+        if self.results.is_empty() {
+            return write!(f, "No tests were found");
+        }
+        let failed = self.failures().count();
+        write!(f, "{} passed, {failed} failed", self.results.len() - failed)
+    }
+}
+
+/// Finds the exposed top-level values annotated with `Test` in the Elm modules of the given directory.
+/// Returns them fully qualified.
+pub(crate) fn find_tests(directory: &Path) -> Result<Vec<String>> {
+    let mut tests = Vec::new();
+    let entries = fs::read_dir(directory).map_err(Error::map_disk_error(directory.to_owned()))?;
+    for entry in entries {
+        let path = entry
+            .map_err(Error::map_disk_error(directory.to_owned()))?
+            .path();
+        if path.is_dir() {
+            tests.extend(find_tests(&path)?);
+        } else if path.extension().is_some_and(|extension| extension == "elm") {
+            let source = fs::read_to_string(&path).map_err(Error::map_disk_error(path.clone()))?;
+            tests.extend(module_tests(&source));
+        }
+    }
+    tests.sort();
+    Ok(tests)
+}
+
+fn module_tests(source: &str) -> Vec<String> {
+    let Some((module_name, exposing_list)) = module_header(source) else {
+        return Vec::new();
+    };
+    let exposed = source[exposing_list.start + 1..exposing_list.end - 1]
+        .split(',')
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    declarations(source)
+        .iter()
+        .filter_map(|declaration| {
+            let (name, annotation) = declaration.split_once(':')?;
+            let name = name.trim_end();
+            let is_value = name
+                .chars()
+                .next()
+                .is_some_and(|first| first.is_lowercase())
+                && name
+                    .chars()
+                    .all(|char| char.is_alphanumeric() || char == '_');
+            let is_test = matches!(annotation.trim(), "Test" | "Test.Test");
+            let is_exposed = exposed.contains(&"..") || exposed.contains(&name);
+            (is_value && is_test && is_exposed).then(|| format!("{module_name}.{name}"))
+        })
+        .collect()
+}
+
+/// The top-level declarations of the source, each with its indented lines joined into one,
+/// so annotations spanning multiple lines (e.g. `suite :\n    Test`) are found as well.
+fn declarations(source: &str) -> Vec<String> {
+    let mut declarations: Vec<String> = Vec::new();
+    for line in source.lines().filter(|line| !line.trim().is_empty()) {
+        match declarations.last_mut() {
+            Some(declaration) if line.starts_with(char::is_whitespace) => {
+                declaration.push(' ');
+                declaration.push_str(line.trim());
+            }
+            _ => declarations.push(line.to_owned()),
+        }
+    }
+    declarations
+}
+
+/// A function running the given tests with a seed and the number of fuzz runs.
+pub(crate) fn run_tests(tests: &[String]) -> String {
+    let tests = tests
+        .iter()
+        .map(|test| format!("( \"{test}\", {test} )"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("\\seed runs -> List.concatMap (runTest seed runs) [ {tests} ]")
+}
+
+/// The seed and the number of fuzz runs, along with the declarations needed by `run_tests`.
+pub(crate) fn tests_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Random");
+    dependencies.import("Test.Runner");
+    dependencies.import("Test.Runner.Failure");
+    if dependencies.declare("runTest") {
+        dependencies.define("runTest", RUN_TEST.to_owned());
+        dependencies.define("formatFailure", FORMAT_FAILURE.to_owned());
+    }
+    Ok(vec![
+        PortType::Native("Int".to_owned()),
+        PortType::Native("Int".to_owned()),
+    ])
+}
+
+pub(crate) fn results_output(
+    _format_adjustment: fn(String) -> String,
+    _dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    Ok(PortType::Native(
+        "List { labels : List String, failures : List String, mode : String }".to_owned(),
+    ))
+}
+
+/// Runs all tests of a top-level `Test`, labelled with its name.
+/// Not annotated, so `Test` does not need to be imported, which could clash with a `Test` module of the project.
+const RUN_TEST: &str = r#"runTest seed runs ( name, test ) =
+    let
+        run mode runner =
+            { labels = name :: List.reverse runner.labels
+            , failures =
+                runner.run ()
+                    |> List.filterMap Test.Runner.getFailureReason
+                    |> List.map formatFailure
+            , mode = mode
+            }
+    in
+    case Test.Runner.fromTest runs (Random.initialSeed seed) test of
+        Test.Runner.Plain runners ->
+            List.map (run "plain") runners
+
+        Test.Runner.Only runners ->
+            List.map (run "only") runners
+
+        Test.Runner.Skipping runners ->
+            List.map (run "skipping") runners
+
+        Test.Runner.Invalid message ->
+            [ { labels = [ name ], failures = [ message ], mode = "invalid" } ]"#;
+
+/// Renders a failure like `elm-test`, including the fuzzed input if present.
+const FORMAT_FAILURE: &str = r#"formatFailure : { given : Maybe String, description : String, reason : Test.Runner.Failure.Reason } -> String
+formatFailure { given, description, reason } =
+    let
+        message =
+            Test.Runner.Failure.format description reason
+    in
+    case given of
+        Just value ->
+            "Given " ++ value ++ "\n\n" ++ message
+
+        Nothing ->
+            message"#;
+
+#[cfg(test)]
+mod tests {
+    use super::{module_tests, run_tests, ElmTestReport};
+
+    #[test]
+    fn exposed_tests() {
+        let source = "module MathTest exposing (suite, other)
+
+import Test exposing (Test)
+
+
+suite : Test
+suite =
+    Test.todo \"suite\"
+
+
+other : Test.Test
+other =
+    Test.todo \"other\"
+
+
+hidden : Test
+hidden =
+    Test.todo \"hidden\"
+
+
+helper : Int -> Test
+helper _ =
+    Test.todo \"helper\"
+";
+        assert_eq!(module_tests(source), ["MathTest.suite", "MathTest.other"]);
+    }
+
+    #[test]
+    fn all_exposed() {
+        let source = "module A exposing (..)\n\na : Test\na =\n    Test.todo \"a\"\n";
+        assert_eq!(module_tests(source), ["A.a"]);
+    }
+
+    #[test]
+    fn multi_line_annotation() {
+        let source = "module A exposing (a, b)\n\na :\n    Test\na =\n    Test.todo \"a\"\n\n\nb :\n    Int\n    -> Test\nb _ =\n    Test.todo \"b\"\n";
+        assert_eq!(module_tests(source), ["A.a"]);
+    }
+
+    #[test]
+    fn empty_report() {
+        let report = ElmTestReport {
+            results: Vec::new(),
+        };
+        assert!(!report.passed());
+        assert_eq!(report.to_string(), "No tests were found");
+    }
+
+    #[test]
+    fn run_tests_expression() {
+        assert_eq!(
+            run_tests(&["A.a".to_owned()]),
+            "\\seed runs -> List.concatMap (runTest seed runs) [ ( \"A.a\", A.a ) ]"
+        );
+    }
+}
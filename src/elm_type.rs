@@ -1,38 +1,166 @@
-use std::{convert::identity, sync::LazyLock};
+use std::{any::TypeId, convert::identity, sync::LazyLock};
 
-use crate::error::Result;
-use serde::Deserialize;
+use crate::error::{Error, Result};
+use serde::{de::DeserializeOwned, Deserialize};
 use serde_reflection::{ContainerFormat, Format, Named, Registry, Samples};
 
+pub mod codec;
+#[cfg(feature = "schemars")]
+mod schema;
+
+pub use codec::{ElmDependencies, ElmType};
+
 static SAMPLES: LazyLock<Samples> = LazyLock::new(Samples::new);
 
+/// Rust types whose Elm counterpart can be inferred.
+///
+/// The Elm type is inferred by tracing the `Deserialize` implementation via `serde-reflection`,
+/// so this is implemented for every (`'static`) `DeserializeOwned` type.
+/// With the `schemars` feature, `ElmRoot::prepare_with_schema` infers it from the `JsonSchema` implementation instead.
+pub trait ElmCompatible: DeserializeOwned + 'static {}
+
+impl<T: DeserializeOwned + 'static> ElmCompatible for T {}
+
+pub fn infer<T: ElmCompatible>(format_adjustment: impl Fn(String) -> String) -> Result<String> {
+    convert::<T>(format_adjustment)
+}
+
+/// Infers the Elm type from the `JsonSchema` implementation, which (unlike tracing)
+/// supports untagged enums, `#[serde(flatten)]` and custom `Deserialize` implementations.
+/// Parts of the schema without a structural Elm counterpart are mapped to `Json.Decode.Value`.
+#[cfg(feature = "schemars")]
+pub fn infer_with_schema<T: ElmCompatible + schemars::JsonSchema>(
+    format_adjustment: impl Fn(String) -> String,
+) -> Result<String> {
+    schema::convert::<T>(format_adjustment)
+}
+
+/// How values of a Rust type cross the ports of the generated binding.
+pub enum PortType {
+    /// Passed as is, relying on the automatic conversion of Elm ports into the given (inferred) Elm type.
+    Native(String),
+    /// Passed as Json and converted with the codecs of an `ElmType` implementation.
+    /// Only types deriving `ElmType` are passed this way.
+    #[cfg_attr(not(feature = "derive"), allow(dead_code))]
+    Codec { decoder: String, encoder: String },
+}
+
+impl PortType {
+    pub fn input_annotation(&self) -> &str {
+        match self {
+            PortType::Native(elm_type) => elm_type,
+            PortType::Codec { .. } => "Json.Decode.Value",
+        }
+    }
+
+    pub fn output_annotation(&self) -> &str {
+        match self {
+            PortType::Native(elm_type) => elm_type,
+            PortType::Codec { .. } => "Json.Encode.Value",
+        }
+    }
+}
+
+/// Determine how values of type `T` are passed to Elm, preferring a derived `ElmType` over inference.
+pub fn port_type<T: ElmCompatible>(
+    format_adjustment: impl Fn(String) -> String,
+    dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    port_type_inferred_by::<T>(|| infer::<T>(format_adjustment), dependencies)
+}
+
+/// Like `port_type`, but inferring the Elm type from the `JsonSchema` implementation.
+#[cfg(feature = "schemars")]
+pub fn schema_port_type<T: ElmCompatible + schemars::JsonSchema>(
+    format_adjustment: impl Fn(String) -> String,
+    dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    port_type_inferred_by::<T>(|| infer_with_schema::<T>(format_adjustment), dependencies)
+}
+
+fn port_type_inferred_by<T: ElmCompatible>(
+    infer: impl FnOnce() -> Result<String>,
+    dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    #[cfg(feature = "derive")]
+    if let Some(element) = codec::registered::<T>() {
+        dependencies.import("Json.Decode");
+        dependencies.import("Json.Encode");
+        (element.dependencies)(dependencies);
+        return Ok(PortType::Codec {
+            decoder: element.decoder,
+            encoder: element.encoder,
+        });
+    }
+    // Json values are passed through the ports as is, but cannot be traced
+    if TypeId::of::<T>() == TypeId::of::<serde_json::Value>() {
+        dependencies.import("Json.Decode");
+        return Ok(PortType::Native("Json.Decode.Value".to_owned()));
+    }
+    let elm_type = infer()?;
+    if elm_type.contains("Json.Decode.") {
+        dependencies.import("Json.Decode");
+    }
+    Ok(PortType::Native(elm_type))
+}
+
+/// Determine how lists of values of type `T` are passed to Elm.
+pub fn list_port_type<T: ElmCompatible>(
+    format_adjustment: impl Fn(String) -> String,
+    dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    Ok(
+        match port_type::<T>(wrap_in_round_brackets, dependencies)? {
+            PortType::Native(elm_type) => {
+                PortType::Native(format_adjustment(format!("List {elm_type}")))
+            }
+            PortType::Codec { decoder, encoder } => PortType::Codec {
+                decoder: format!("(Json.Decode.list {decoder})"),
+                encoder: format!("(Json.Encode.list {encoder})"),
+            },
+        },
+    )
+}
+
 pub fn convert<'de, T: Deserialize<'de>>(
     format_adjustment: impl Fn(String) -> String,
 ) -> Result<String> {
     let mut tracer = serde_reflection::Tracer::new(Default::default());
     let (format, _) = tracer.trace_type_once::<T>(&SAMPLES)?;
-    let registry = tracer.registry()?;
+    // Enums can not be traced with a single sample, so their variants are missing
+    let registry = tracer.registry().map_err(|error| match error {
+        serde_reflection::Error::MissingVariants(names) => unsupported_enum(&names.join(", ")),
+        error => error.into(),
+    })?;
 
-    Ok(convert_format(format, &registry, format_adjustment))
+    convert_format(format, &registry, format_adjustment)
 }
 
 fn convert_format(
     format: Format,
     registry: &Registry,
     format_adjustment: impl Fn(String) -> String,
-) -> String {
-    match format {
-        Format::Variable(_) => panic!("Unknown format"),
+) -> Result<String> {
+    Ok(match format {
+        Format::Variable(_) => {
+            return Err(Box::new(Error::UnsupportedType(
+                "The type could not be traced completely".to_owned(),
+            )))
+        }
         Format::TypeName(type_name) => {
-            let referenced_format = registry.get(&type_name).unwrap();
+            let Some(referenced_format) = registry.get(&type_name) else {
+                return Err(Box::new(Error::UnsupportedType(format!(
+                    "{type_name} could not be traced"
+                ))));
+            };
             match referenced_format {
                 ContainerFormat::UnitStruct => "()".to_owned(),
                 ContainerFormat::NewTypeStruct(inner) => {
-                    convert_format(*inner.clone(), registry, format_adjustment)
+                    convert_format(*inner.clone(), registry, format_adjustment)?
                 }
-                ContainerFormat::TupleStruct(vec) => convert_tuple_format(vec.clone(), registry),
-                ContainerFormat::Struct(vec) => convert_struct_format(vec.clone(), registry),
-                ContainerFormat::Enum(_) => todo!("Enums are not supported"),
+                ContainerFormat::TupleStruct(vec) => convert_tuple_format(vec.clone(), registry)?,
+                ContainerFormat::Struct(vec) => convert_struct_format(vec.clone(), registry)?,
+                ContainerFormat::Enum(_) => return Err(unsupported_enum(&type_name)),
             }
         }
         Format::Unit => "()".to_owned(),
@@ -54,53 +182,60 @@ fn convert_format(
         Format::Bytes => "Bytes".to_owned(),
         Format::Option(inner) => format_adjustment(format!(
             "Maybe {}",
-            convert_format(*inner, registry, wrap_in_round_brackets)
+            convert_format(*inner, registry, wrap_in_round_brackets)?
         )),
         Format::Seq(inner) => format_adjustment(format!(
             "List {}",
-            convert_format(*inner, registry, wrap_in_round_brackets)
+            convert_format(*inner, registry, wrap_in_round_brackets)?
         )),
         Format::Map { key, value } => format_adjustment(format!(
             "Dict {} {}",
-            convert_format(*key, registry, wrap_in_round_brackets),
-            convert_format(*value, registry, wrap_in_round_brackets)
+            convert_format(*key, registry, wrap_in_round_brackets)?,
+            convert_format(*value, registry, wrap_in_round_brackets)?
         )),
-        Format::Tuple(vec) => convert_tuple_format(vec, registry),
+        Format::Tuple(vec) => convert_tuple_format(vec, registry)?,
         Format::TupleArray { content, size: _ } => {
             format!(
                 "List {}",
-                convert_format(*content, registry, wrap_in_round_brackets)
+                convert_format(*content, registry, wrap_in_round_brackets)?
             )
         }
-    }
+    })
 }
 
-fn convert_tuple_format(vec: Vec<Format>, registry: &Registry) -> String {
+fn unsupported_enum(name: &str) -> Box<Error> {
+    Box::new(Error::UnsupportedType(format!(
+        "The enum {name} has no inferred Elm counterpart, derive ElmType for it \
+         (and use it directly, in an Option, Box, Vec or String keyed map)"
+    )))
+}
+
+fn convert_tuple_format(vec: Vec<Format>, registry: &Registry) -> Result<String> {
     let types = vec
         .into_iter()
         .map(|inner| convert_format(inner, registry, identity))
-        .collect::<Vec<_>>()
+        .collect::<Result<Vec<_>>>()?
         .join(", ");
-    format!("( {types} )")
+    Ok(format!("( {types} )"))
 }
 
 pub fn wrap_in_round_brackets(str: String) -> String {
     format!("({str})")
 }
 
-fn convert_struct_format(vec: Vec<Named<Format>>, registry: &Registry) -> String {
+fn convert_struct_format(vec: Vec<Named<Format>>, registry: &Registry) -> Result<String> {
     let types = vec
         .into_iter()
         .map(|inner| {
-            format!(
+            Ok(format!(
                 "{} : {}",
                 inner.name,
-                convert_format(inner.value, registry, identity)
-            )
+                convert_format(inner.value, registry, identity)?
+            ))
         })
-        .collect::<Vec<_>>()
+        .collect::<Result<Vec<_>>>()?
         .join(", ");
-    format!("{{ {types} }}")
+    Ok(format!("{{ {types} }}"))
 }
 
 #[cfg(test)]
@@ -109,9 +244,20 @@ mod tests {
 
     use serde::Deserialize;
 
-    use crate::elm_type::wrap_in_round_brackets;
+    use crate::{elm_type::wrap_in_round_brackets, Error};
+
+    use super::{convert, list_port_type, port_type, ElmDependencies, PortType};
 
-    use super::convert;
+    #[test]
+    fn enums_are_not_traced() {
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        enum Role {
+            Admin,
+        }
+        let error = convert::<(Role, i32)>(identity).unwrap_err();
+        assert!(matches!(*error, Error::UnsupportedType(message) if message.contains("Role")));
+    }
 
     #[test]
     fn simple_struct() {
@@ -193,4 +339,19 @@ mod tests {
             "Maybe { c : { a : Int, b : Bool }, d : List { a : Int, b : Bool } }"
         );
     }
+
+    #[test]
+    fn json_value_port_type() {
+        let mut dependencies = ElmDependencies::default();
+        let port_type = port_type::<serde_json::Value>(identity, &mut dependencies).unwrap();
+        assert!(matches!(port_type, PortType::Native(elm_type) if elm_type == "Json.Decode.Value"));
+        assert_eq!(dependencies.imports().collect::<Vec<_>>(), ["Json.Decode"]);
+    }
+
+    #[test]
+    fn list_port_type_wraps_elements() {
+        let mut dependencies = ElmDependencies::default();
+        let port_type = list_port_type::<Option<u8>>(identity, &mut dependencies).unwrap();
+        assert!(matches!(port_type, PortType::Native(elm_type) if elm_type == "List (Maybe Int)"));
+    }
 }
@@ -0,0 +1,879 @@
+#[cfg(feature = "derive")]
+use std::any::TypeId;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Explicit mapping of a Rust type to an Elm type, including Json codecs for it.
+///
+/// Usually you do not implement this by hand, but via `#[derive(ElmType)]` (with the `derive` feature enabled).
+/// Types implementing this trait do not have to be traced, since the Elm type and the codecs are known upfront.
+/// This also allows mapping Rust enums to Elm custom types.
+pub trait ElmType {
+    /// The Elm type annotation, e.g. `Int`, `List (Maybe String)` or `Api.User.User`.
+    fn elm_type() -> String;
+
+    /// An Elm expression of type `Json.Decode.Decoder a`, decoding the serde_json representation of this type.
+    fn elm_decoder() -> String;
+
+    /// An Elm expression of type `a -> Json.Encode.Value`, producing the serde_json representation of this type.
+    fn elm_encoder() -> String;
+
+    /// Registers the Elm modules and top-level declarations the annotation and codecs refer to.
+    fn elm_dependencies(_dependencies: &mut ElmDependencies) {}
+}
+
+/// Elm modules and top-level declarations needed by the annotations and codecs of `ElmType` implementations.
+#[derive(Default)]
+pub struct ElmDependencies {
+    modules: BTreeSet<String>,
+    declarations: BTreeMap<String, String>,
+}
+
+impl ElmDependencies {
+    /// Import the given Elm module in the generated binding.
+    pub fn import(&mut self, module: impl Into<String>) {
+        self.modules.insert(module.into());
+    }
+
+    /// Add a top-level declaration with the given name to the generated binding.
+    ///
+    /// Returns `false` if a declaration with this name was already added.
+    /// The code can also be added later via `define`, which is needed for recursive types.
+    pub fn declare(&mut self, name: &str) -> bool {
+        if self.declarations.contains_key(name) {
+            return false;
+        }
+        self.declarations.insert(name.to_owned(), String::new());
+        true
+    }
+
+    /// Set the code of a top-level declaration.
+    pub fn define(&mut self, name: &str, code: String) {
+        self.declarations.insert(name.to_owned(), code);
+    }
+
+    pub(crate) fn imports(&self) -> impl Iterator<Item = &str> {
+        self.modules.iter().map(String::as_str)
+    }
+
+    pub(crate) fn declarations(&self) -> String {
+        self.declarations
+            .values()
+            .map(|code| format!("\n\n{code}"))
+            .collect()
+    }
+}
+
+macro_rules! primitive {
+    ($elm_type: literal, $json_type: literal, $($rust_type: ty),*) => {
+        $(
+            impl ElmType for $rust_type {
+                fn elm_type() -> String {
+                    $elm_type.to_owned()
+                }
+
+                fn elm_decoder() -> String {
+                    concat!("Json.Decode.", $json_type).to_owned()
+                }
+
+                fn elm_encoder() -> String {
+                    concat!("Json.Encode.", $json_type).to_owned()
+                }
+            }
+        )*
+    };
+}
+
+primitive!("Int", "int", i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+primitive!("Float", "float", f32, f64);
+primitive!("Bool", "bool", bool);
+primitive!("String", "string", String);
+
+impl ElmType for char {
+    fn elm_type() -> String {
+        "Char".to_owned()
+    }
+
+    fn elm_decoder() -> String {
+        "(Json.Decode.string |> Json.Decode.andThen (\\s -> String.uncons s |> Maybe.map (Tuple.first >> Json.Decode.succeed) |> Maybe.withDefault (Json.Decode.fail \"Expected a single character\")))".to_owned()
+    }
+
+    fn elm_encoder() -> String {
+        "(String.fromChar >> Json.Encode.string)".to_owned()
+    }
+}
+
+impl ElmType for () {
+    fn elm_type() -> String {
+        "()".to_owned()
+    }
+
+    fn elm_decoder() -> String {
+        "(Json.Decode.null ())".to_owned()
+    }
+
+    fn elm_encoder() -> String {
+        "(\\_ -> Json.Encode.null)".to_owned()
+    }
+}
+
+impl ElmType for serde_json::Value {
+    fn elm_type() -> String {
+        "Json.Decode.Value".to_owned()
+    }
+
+    fn elm_decoder() -> String {
+        "Json.Decode.value".to_owned()
+    }
+
+    fn elm_encoder() -> String {
+        "identity".to_owned()
+    }
+}
+
+impl<T: ElmType> ElmType for Option<T> {
+    fn elm_type() -> String {
+        format!("Maybe ({})", T::elm_type())
+    }
+
+    fn elm_decoder() -> String {
+        format!("(Json.Decode.nullable {})", T::elm_decoder())
+    }
+
+    fn elm_encoder() -> String {
+        format!(
+            "(Maybe.map {} >> Maybe.withDefault Json.Encode.null)",
+            T::elm_encoder()
+        )
+    }
+
+    fn elm_dependencies(dependencies: &mut ElmDependencies) {
+        T::elm_dependencies(dependencies);
+    }
+}
+
+impl<T: ElmType> ElmType for Box<T> {
+    fn elm_type() -> String {
+        T::elm_type()
+    }
+
+    fn elm_decoder() -> String {
+        T::elm_decoder()
+    }
+
+    fn elm_encoder() -> String {
+        T::elm_encoder()
+    }
+
+    fn elm_dependencies(dependencies: &mut ElmDependencies) {
+        T::elm_dependencies(dependencies);
+    }
+}
+
+macro_rules! list {
+    ($($rust_type: ty $(, const $size: ident)?);*) => {
+        $(
+            impl<T: ElmType $(, const $size: usize)?> ElmType for $rust_type {
+                fn elm_type() -> String {
+                    format!("List ({})", T::elm_type())
+                }
+
+                fn elm_decoder() -> String {
+                    format!("(Json.Decode.list {})", T::elm_decoder())
+                }
+
+                fn elm_encoder() -> String {
+                    format!("(Json.Encode.list {})", T::elm_encoder())
+                }
+
+                fn elm_dependencies(dependencies: &mut ElmDependencies) {
+                    T::elm_dependencies(dependencies);
+                }
+            }
+        )*
+    };
+}
+
+list!(Vec<T>; [T; N], const N);
+
+macro_rules! dict {
+    ($($rust_type: ty),*) => {
+        $(
+            impl<V: ElmType> ElmType for $rust_type {
+                fn elm_type() -> String {
+                    format!("Dict.Dict String ({})", V::elm_type())
+                }
+
+                fn elm_decoder() -> String {
+                    format!("(Json.Decode.dict {})", V::elm_decoder())
+                }
+
+                fn elm_encoder() -> String {
+                    format!("(Json.Encode.dict identity {})", V::elm_encoder())
+                }
+
+                fn elm_dependencies(dependencies: &mut ElmDependencies) {
+                    dependencies.import("Dict");
+                    V::elm_dependencies(dependencies);
+                }
+            }
+        )*
+    };
+}
+
+dict!(HashMap<String, V>, BTreeMap<String, V>);
+
+impl<A: ElmType, B: ElmType> ElmType for (A, B) {
+    fn elm_type() -> String {
+        format!("( {}, {} )", A::elm_type(), B::elm_type())
+    }
+
+    fn elm_decoder() -> String {
+        format!(
+            "(Json.Decode.map2 Tuple.pair (Json.Decode.index 0 {}) (Json.Decode.index 1 {}))",
+            A::elm_decoder(),
+            B::elm_decoder()
+        )
+    }
+
+    fn elm_encoder() -> String {
+        format!("(encodeTuple2 {} {})", A::elm_encoder(), B::elm_encoder())
+    }
+
+    fn elm_dependencies(dependencies: &mut ElmDependencies) {
+        if dependencies.declare("encodeTuple2") {
+            dependencies.define(
+                "encodeTuple2",
+                "encodeTuple2 encodeA encodeB ( a, b ) =\n    Json.Encode.list identity [ encodeA a, encodeB b ]"
+                    .to_owned(),
+            );
+        }
+        A::elm_dependencies(dependencies);
+        B::elm_dependencies(dependencies);
+    }
+}
+
+impl<A: ElmType, B: ElmType, C: ElmType> ElmType for (A, B, C) {
+    fn elm_type() -> String {
+        format!(
+            "( {}, {}, {} )",
+            A::elm_type(),
+            B::elm_type(),
+            C::elm_type()
+        )
+    }
+
+    fn elm_decoder() -> String {
+        format!(
+            "(Json.Decode.map3 (\\a b c -> ( a, b, c )) (Json.Decode.index 0 {}) (Json.Decode.index 1 {}) (Json.Decode.index 2 {}))",
+            A::elm_decoder(),
+            B::elm_decoder(),
+            C::elm_decoder()
+        )
+    }
+
+    fn elm_encoder() -> String {
+        format!(
+            "(encodeTuple3 {} {} {})",
+            A::elm_encoder(),
+            B::elm_encoder(),
+            C::elm_encoder()
+        )
+    }
+
+    fn elm_dependencies(dependencies: &mut ElmDependencies) {
+        if dependencies.declare("encodeTuple3") {
+            dependencies.define(
+                "encodeTuple3",
+                "encodeTuple3 encodeA encodeB encodeC ( a, b, c ) =\n    Json.Encode.list identity [ encodeA a, encodeB b, encodeC c ]"
+                    .to_owned(),
+            );
+        }
+        A::elm_dependencies(dependencies);
+        B::elm_dependencies(dependencies);
+        C::elm_dependencies(dependencies);
+    }
+}
+
+/// Description of a type deriving `ElmType`, from which the annotation and codecs are rendered.
+/// Only meant to be constructed by the derive macro.
+#[doc(hidden)]
+pub struct Definition {
+    /// Unique name of the Rust type (including the location of its definition), used to name the generated codec declarations.
+    pub key: &'static str,
+    /// Elm module defining the type. If absent, the type is mapped structurally.
+    pub elm_module: Option<&'static str>,
+    pub elm_name: &'static str,
+    /// Only evaluated when needed, so recursive types do not recurse infinitely.
+    pub shape: fn() -> Shape,
+}
+
+#[doc(hidden)]
+pub enum Shape {
+    Record(Vec<Field>),
+    Tuple(Vec<Element>),
+    Unit,
+    CustomType(Vec<Variant>),
+}
+
+#[doc(hidden)]
+pub struct Field {
+    pub elm_name: &'static str,
+    pub json_key: &'static str,
+    pub element: Element,
+}
+
+#[doc(hidden)]
+pub struct Element {
+    pub elm_type: String,
+    pub decoder: String,
+    pub encoder: String,
+    pub dependencies: fn(&mut ElmDependencies),
+}
+
+impl Element {
+    pub fn of<T: ElmType + ?Sized>() -> Self {
+        Self {
+            elm_type: T::elm_type(),
+            decoder: T::elm_decoder(),
+            encoder: T::elm_encoder(),
+            dependencies: T::elm_dependencies,
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct Variant {
+    pub elm_name: &'static str,
+    pub json_tag: &'static str,
+    pub shape: VariantShape,
+}
+
+#[doc(hidden)]
+pub enum VariantShape {
+    Unit,
+    Newtype(Element),
+    Tuple(Vec<Element>),
+    Record(Vec<Field>),
+}
+
+impl Definition {
+    pub fn elm_type(&self) -> String {
+        if self.elm_module.is_some() {
+            return self.qualified(self.elm_name);
+        }
+        match (self.shape)() {
+            Shape::Record(fields) => record_type(&fields),
+            Shape::Tuple(elements) => match elements.as_slice() {
+                [element] => element.elm_type.clone(),
+                _ => format!(
+                    "( {} )",
+                    elements
+                        .iter()
+                        .map(|element| element.elm_type.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+            Shape::Unit => "()".to_owned(),
+            Shape::CustomType(_) => unreachable!("Custom types always have an Elm module"),
+        }
+    }
+
+    pub fn elm_decoder(&self) -> String {
+        // Lazy, so recursive types do not result in cyclic values
+        format!("(Json.Decode.lazy (\\_ -> {}))", self.decoder_name())
+    }
+
+    pub fn elm_encoder(&self) -> String {
+        self.encoder_name()
+    }
+
+    pub fn elm_dependencies(&self, dependencies: &mut ElmDependencies) {
+        let decoder_name = self.decoder_name();
+        if !dependencies.declare(&decoder_name) {
+            return;
+        }
+        dependencies.import("Json.Decode");
+        dependencies.import("Json.Encode");
+        if let Some(elm_module) = self.elm_module {
+            dependencies.import(elm_module);
+        }
+        let shape = (self.shape)();
+        for element in elements(&shape) {
+            (element.dependencies)(dependencies);
+        }
+
+        dependencies.define(
+            &decoder_name,
+            format!("{decoder_name} =\n    {}", self.decoder_body(&shape)),
+        );
+        let encoder_name = self.encoder_name();
+        let (pattern, body) = self.encoder_pattern_and_body(&shape);
+        dependencies.define(
+            &encoder_name,
+            format!("{encoder_name} {pattern} =\n    {body}"),
+        );
+    }
+
+    fn decoder_name(&self) -> String {
+        format!("decode_{}", self.key.replace("::", "_"))
+    }
+
+    fn encoder_name(&self) -> String {
+        format!("encode_{}", self.key.replace("::", "_"))
+    }
+
+    fn qualified(&self, name: &str) -> String {
+        match self.elm_module {
+            Some(elm_module) => format!("{elm_module}.{name}"),
+            None => name.to_owned(),
+        }
+    }
+
+    fn decoder_body(&self, shape: &Shape) -> String {
+        match shape {
+            Shape::Record(fields) => record_decoder(fields),
+            Shape::Tuple(elements) => match (elements.as_slice(), self.elm_module) {
+                ([element], Some(_)) => format!(
+                    "Json.Decode.map {} {}",
+                    self.qualified(self.elm_name),
+                    element.decoder
+                ),
+                ([element], None) => element.decoder.clone(),
+                (_, Some(_)) => tuple_decoder(&self.qualified(self.elm_name), elements),
+                (_, None) => tuple_decoder(&tuple_constructor(elements.len()), elements),
+            },
+            Shape::Unit => format!("Json.Decode.null {}", self.unit_value()),
+            Shape::CustomType(variants) => self.custom_type_decoder(variants),
+        }
+    }
+
+    fn encoder_pattern_and_body(&self, shape: &Shape) -> (String, String) {
+        match shape {
+            Shape::Record(fields) => ("value".to_owned(), record_encoder("value", fields)),
+            Shape::Tuple(elements) => {
+                let pattern = match (elements.len(), self.elm_module) {
+                    (1, None) => "a0".to_owned(),
+                    (len, None) => format!("( {} )", arguments(len).join(", ")),
+                    (len, Some(_)) => format!(
+                        "({} {})",
+                        self.qualified(self.elm_name),
+                        arguments(len).join(" ")
+                    ),
+                };
+                let body = match elements.as_slice() {
+                    [element] => format!("{} a0", element.encoder),
+                    _ => tuple_encoder(elements),
+                };
+                (pattern, body)
+            }
+            Shape::Unit => ("_".to_owned(), "Json.Encode.null".to_owned()),
+            Shape::CustomType(variants) => ("value".to_owned(), self.custom_type_encoder(variants)),
+        }
+    }
+
+    fn unit_value(&self) -> String {
+        match self.elm_module {
+            Some(_) => self.qualified(self.elm_name),
+            None => "()".to_owned(),
+        }
+    }
+
+    fn custom_type_decoder(&self, variants: &[Variant]) -> String {
+        let unit_variants = variants
+            .iter()
+            .filter(|variant| matches!(variant.shape, VariantShape::Unit))
+            .map(|variant| {
+                format!(
+                    "if tag == \"{}\" then Json.Decode.succeed {} else ",
+                    variant.json_tag,
+                    self.qualified(variant.elm_name)
+                )
+            })
+            .collect::<String>();
+        let mut decoders = Vec::new();
+        if !unit_variants.is_empty() {
+            decoders.push(format!(
+                "Json.Decode.string |> Json.Decode.andThen (\\tag -> {unit_variants}Json.Decode.fail (\"Unknown variant \" ++ tag))"
+            ));
+        }
+        for variant in variants {
+            let constructor = self.qualified(variant.elm_name);
+            let decoder = match &variant.shape {
+                VariantShape::Unit => continue,
+                VariantShape::Newtype(element) => {
+                    format!("Json.Decode.map {constructor} {}", element.decoder)
+                }
+                VariantShape::Tuple(elements) => tuple_decoder(&constructor, elements),
+                VariantShape::Record(fields) => {
+                    format!("Json.Decode.map {constructor} ({})", record_decoder(fields))
+                }
+            };
+            decoders.push(format!(
+                "Json.Decode.field \"{}\" ({decoder})",
+                variant.json_tag
+            ));
+        }
+        format!(
+            "Json.Decode.oneOf\n        [ {}\n        ]",
+            decoders.join("\n        , ")
+        )
+    }
+
+    fn custom_type_encoder(&self, variants: &[Variant]) -> String {
+        let branches = variants
+            .iter()
+            .map(|variant| {
+                let constructor = self.qualified(variant.elm_name);
+                let tag = variant.json_tag;
+                let (pattern, body) = match &variant.shape {
+                    VariantShape::Unit => (constructor, format!("Json.Encode.string \"{tag}\"")),
+                    VariantShape::Newtype(element) => (
+                        format!("{constructor} a0"),
+                        format!("{} a0", element.encoder),
+                    ),
+                    VariantShape::Tuple(elements) => (
+                        format!("{constructor} {}", arguments(elements.len()).join(" ")),
+                        tuple_encoder(elements),
+                    ),
+                    VariantShape::Record(fields) => {
+                        (format!("{constructor} a0"), record_encoder("a0", fields))
+                    }
+                };
+                let body = match variant.shape {
+                    VariantShape::Unit => body,
+                    _ => format!("Json.Encode.object [ ( \"{tag}\", {body} ) ]"),
+                };
+                format!("        {pattern} ->\n            {body}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        format!("case value of\n{branches}")
+    }
+}
+
+fn elements(shape: &Shape) -> Vec<&Element> {
+    match shape {
+        Shape::Record(fields) => fields.iter().map(|field| &field.element).collect(),
+        Shape::Tuple(elements) => elements.iter().collect(),
+        Shape::Unit => Vec::new(),
+        Shape::CustomType(variants) => variants
+            .iter()
+            .flat_map(|variant| match &variant.shape {
+                VariantShape::Unit => Vec::new(),
+                VariantShape::Newtype(element) => vec![element],
+                VariantShape::Tuple(elements) => elements.iter().collect(),
+                VariantShape::Record(fields) => fields.iter().map(|field| &field.element).collect(),
+            })
+            .collect(),
+    }
+}
+
+fn arguments(len: usize) -> Vec<String> {
+    (0..len).map(|index| format!("a{index}")).collect()
+}
+
+fn tuple_constructor(len: usize) -> String {
+    let arguments = arguments(len);
+    format!(
+        "(\\{} -> ( {} ))",
+        arguments.join(" "),
+        arguments.join(", ")
+    )
+}
+
+fn record_type(fields: &[Field]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| format!("{} : {}", field.elm_name, field.element.elm_type))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{{ {fields} }}")
+}
+
+fn record_decoder(fields: &[Field]) -> String {
+    let arguments = arguments(fields.len());
+    let assignments = fields
+        .iter()
+        .zip(&arguments)
+        .map(|(field, argument)| format!("{} = {argument}", field.elm_name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let constructor = if fields.is_empty() {
+        "{}".to_owned()
+    } else {
+        format!("(\\{} -> {{ {assignments} }})", arguments.join(" "))
+    };
+    fields.iter().fold(
+        format!("Json.Decode.succeed {constructor}"),
+        |decoder, field| {
+            format!(
+                "{decoder} |> Json.Decode.map2 (|>) (Json.Decode.field \"{}\" {})",
+                field.json_key, field.element.decoder
+            )
+        },
+    )
+}
+
+fn record_encoder(value: &str, fields: &[Field]) -> String {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            format!(
+                "( \"{}\", {} {value}.{} )",
+                field.json_key, field.element.encoder, field.elm_name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("Json.Encode.object [ {fields} ]")
+}
+
+fn tuple_decoder(constructor: &str, elements: &[Element]) -> String {
+    elements.iter().enumerate().fold(
+        format!("Json.Decode.succeed {constructor}"),
+        |decoder, (index, element)| {
+            format!(
+                "{decoder} |> Json.Decode.map2 (|>) (Json.Decode.index {index} {})",
+                element.decoder
+            )
+        },
+    )
+}
+
+fn tuple_encoder(elements: &[Element]) -> String {
+    let elements = elements
+        .iter()
+        .zip(arguments(elements.len()))
+        .map(|(element, argument)| format!("{} {argument}", element.encoder))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("Json.Encode.list identity [ {elements} ]")
+}
+
+/// All types deriving `ElmType`, so `ElmRoot::prepare` can use their mapping instead of inferring one.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static ELM_TYPES: [Registration];
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub struct Registration {
+    element: fn(TypeId) -> Option<Element>,
+}
+
+#[cfg(feature = "derive")]
+impl Registration {
+    pub const fn of<T: ElmType + 'static>() -> Self {
+        Self {
+            element: registered_element::<T>,
+        }
+    }
+}
+
+/// The mapping of `T` if it has the given `TypeId`. Common containers of `T` (e.g. `Vec<T>` or `Option<T>`)
+/// are found as well, since only the derived type itself is registered.
+/// Other shapes (e.g. tuples) are traced instead, which fails with `Error::UnsupportedType` for enums.
+#[cfg(feature = "derive")]
+fn registered_element<T: ElmType + 'static>(type_id: TypeId) -> Option<Element> {
+    fn element_of<U: ElmType + 'static>(type_id: TypeId) -> Option<Element> {
+        (TypeId::of::<U>() == type_id).then(Element::of::<U>)
+    }
+    element_of::<T>(type_id)
+        .or_else(|| element_of::<Option<T>>(type_id))
+        .or_else(|| element_of::<Box<T>>(type_id))
+        .or_else(|| element_of::<Option<Box<T>>>(type_id))
+        .or_else(|| element_of::<Vec<T>>(type_id))
+        .or_else(|| element_of::<Vec<Option<T>>>(type_id))
+        .or_else(|| element_of::<Option<Vec<T>>>(type_id))
+        .or_else(|| element_of::<Vec<Vec<T>>>(type_id))
+        .or_else(|| element_of::<HashMap<String, T>>(type_id))
+        .or_else(|| element_of::<BTreeMap<String, T>>(type_id))
+        .or_else(|| element_of::<HashMap<String, Vec<T>>>(type_id))
+        .or_else(|| element_of::<BTreeMap<String, Vec<T>>>(type_id))
+}
+
+#[cfg(feature = "derive")]
+pub(crate) fn registered<T: ?Sized + 'static>() -> Option<Element> {
+    let type_id = TypeId::of::<T>();
+    ELM_TYPES
+        .iter()
+        .find_map(|registration| (registration.element)(type_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    #[cfg(feature = "derive")]
+    use super::registered;
+    use super::{ElmDependencies, ElmType};
+
+    #[test]
+    fn nested_containers() {
+        assert_eq!(<Vec<Option<i32>>>::elm_type(), "List (Maybe (Int))");
+        assert_eq!(
+            <Vec<Option<i32>>>::elm_decoder(),
+            "(Json.Decode.list (Json.Decode.nullable Json.Decode.int))"
+        );
+        assert_eq!(
+            <Vec<Option<i32>>>::elm_encoder(),
+            "(Json.Encode.list (Maybe.map Json.Encode.int >> Maybe.withDefault Json.Encode.null))"
+        );
+    }
+
+    #[test]
+    fn dict_import() {
+        let mut dependencies = ElmDependencies::default();
+        <HashMap<String, bool>>::elm_dependencies(&mut dependencies);
+        assert_eq!(
+            <HashMap<String, bool>>::elm_type(),
+            "Dict.Dict String (Bool)"
+        );
+        assert_eq!(dependencies.imports().collect::<Vec<_>>(), vec!["Dict"]);
+    }
+
+    #[test]
+    fn tuple_helper_declared_once() {
+        let mut dependencies = ElmDependencies::default();
+        <((u8, u8), (u8, u8))>::elm_dependencies(&mut dependencies);
+        assert_eq!(
+            <((u8, u8), (u8, u8))>::elm_encoder(),
+            "(encodeTuple2 (encodeTuple2 Json.Encode.int Json.Encode.int) (encodeTuple2 Json.Encode.int Json.Encode.int))"
+        );
+        assert_eq!(
+            dependencies.declarations(),
+            "\n\nencodeTuple2 encodeA encodeB ( a, b ) =\n    Json.Encode.list identity [ encodeA a, encodeB b ]"
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_record() {
+        #[derive(crate::ElmType, serde::Serialize)]
+        #[serde(rename_all = "camelCase")]
+        #[allow(dead_code)]
+        struct User {
+            user_name: String,
+            #[elm(name = "userAge")]
+            age: Option<u8>,
+        }
+        let mut dependencies = ElmDependencies::default();
+        User::elm_dependencies(&mut dependencies);
+
+        assert!(registered::<User>().is_some());
+        assert!(registered::<Vec<Option<User>>>().is_some());
+        assert!(registered::<HashMap<String, Vec<User>>>().is_some());
+        assert!(registered::<i32>().is_none());
+
+        let key = User::elm_encoder().replacen("encode_", "", 1);
+        assert!(key.starts_with("elm_rust_binding_elm_type_codec_tests_User_"));
+
+        assert_eq!(
+            User::elm_type(),
+            "{ user_name : String, userAge : Maybe (Int) }"
+        );
+        assert_eq!(
+            User::elm_decoder(),
+            "(Json.Decode.lazy (\\_ -> decode_{key}))".replace("{key}", &key)
+        );
+        assert_eq!(
+            dependencies.declarations(),
+            r#"
+
+decode_{key} =
+    Json.Decode.succeed (\a0 a1 -> { user_name = a0, userAge = a1 }) |> Json.Decode.map2 (|>) (Json.Decode.field "userName" Json.Decode.string) |> Json.Decode.map2 (|>) (Json.Decode.field "age" (Json.Decode.nullable Json.Decode.int))
+
+encode_{key} value =
+    Json.Encode.object [ ( "userName", Json.Encode.string value.user_name ), ( "age", (Maybe.map Json.Encode.int >> Maybe.withDefault Json.Encode.null) value.userAge ) ]"#
+                .replace("{key}", &key)
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_custom_type() {
+        #[derive(crate::ElmType)]
+        #[elm(module = "Api.Shape")]
+        #[allow(dead_code)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Line(i32, i32),
+            Rectangle { width: f64, height: f64 },
+        }
+        let mut dependencies = ElmDependencies::default();
+        Shape::elm_dependencies(&mut dependencies);
+
+        let key = Shape::elm_encoder().replacen("encode_", "", 1);
+        assert_eq!(Shape::elm_type(), "Api.Shape.Shape");
+        assert_eq!(
+            dependencies.imports().collect::<Vec<_>>(),
+            vec!["Api.Shape", "Json.Decode", "Json.Encode"]
+        );
+        assert_eq!(
+            dependencies.declarations(),
+            r#"
+
+decode_{key} =
+    Json.Decode.oneOf
+        [ Json.Decode.string |> Json.Decode.andThen (\tag -> if tag == "Empty" then Json.Decode.succeed Api.Shape.Empty else Json.Decode.fail ("Unknown variant " ++ tag))
+        , Json.Decode.field "Circle" (Json.Decode.map Api.Shape.Circle Json.Decode.float)
+        , Json.Decode.field "Line" (Json.Decode.succeed Api.Shape.Line |> Json.Decode.map2 (|>) (Json.Decode.index 0 Json.Decode.int) |> Json.Decode.map2 (|>) (Json.Decode.index 1 Json.Decode.int))
+        , Json.Decode.field "Rectangle" (Json.Decode.map Api.Shape.Rectangle (Json.Decode.succeed (\a0 a1 -> { width = a0, height = a1 }) |> Json.Decode.map2 (|>) (Json.Decode.field "width" Json.Decode.float) |> Json.Decode.map2 (|>) (Json.Decode.field "height" Json.Decode.float)))
+        ]
+
+encode_{key} value =
+    case value of
+        Api.Shape.Empty ->
+            Json.Encode.string "Empty"
+
+        Api.Shape.Circle a0 ->
+            Json.Encode.object [ ( "Circle", Json.Encode.float a0 ) ]
+
+        Api.Shape.Line a0 a1 ->
+            Json.Encode.object [ ( "Line", Json.Encode.list identity [ Json.Encode.int a0, Json.Encode.int a1 ] ) ]
+
+        Api.Shape.Rectangle a0 ->
+            Json.Encode.object [ ( "Rectangle", Json.Encode.object [ ( "width", Json.Encode.float a0.width ), ( "height", Json.Encode.float a0.height ) ] ) ]"#
+                .replace("{key}", &key)
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_recursive_type() {
+        #[derive(crate::ElmType)]
+        #[elm(module = "Tree")]
+        #[allow(dead_code)]
+        struct Tree {
+            children: Vec<Tree>,
+        }
+        let mut dependencies = ElmDependencies::default();
+        Tree::elm_dependencies(&mut dependencies);
+        let key = Tree::elm_encoder().replacen("encode_", "", 1);
+        assert!(dependencies.declarations().contains(
+            &"(Json.Decode.field \"children\" (Json.Decode.list (Json.Decode.lazy (\\_ -> decode_{key}))))"
+                .replace("{key}", &key)
+        ));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_types_with_same_name() {
+        let first = {
+            #[derive(crate::ElmType)]
+            #[allow(dead_code)]
+            struct Local(u8);
+            Local::elm_encoder()
+        };
+        let second = {
+            #[derive(crate::ElmType)]
+            #[allow(dead_code)]
+            struct Local(String);
+            Local::elm_encoder()
+        };
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,335 @@
+use std::convert::identity;
+
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{Map, Value};
+
+use super::wrap_in_round_brackets;
+use crate::error::{Error, Result};
+
+/// Elm type used for everything that has no structural Elm counterpart,
+/// e.g. untagged enums or recursive types. The called Elm function is expected to decode these itself.
+const JSON_VALUE: &str = "Json.Decode.Value";
+
+/// Words which cannot be used as Elm record field names.
+const RESERVED_WORDS: &[&str] = &[
+    "if", "then", "else", "case", "of", "let", "in", "type", "module", "where", "import",
+    "exposing", "as", "port", "effect", "alias", "infix",
+];
+
+pub fn convert<T: JsonSchema>(format_adjustment: impl Fn(String) -> String) -> Result<String> {
+    let root = SchemaGenerator::default()
+        .into_root_schema_for::<T>()
+        .to_value();
+    convert_schema(&root, &root, &mut vec!["#"], format_adjustment)
+}
+
+fn convert_schema<'a>(
+    schema: &'a Value,
+    root: &'a Value,
+    visited_refs: &mut Vec<&'a str>,
+    format_adjustment: impl Fn(String) -> String,
+) -> Result<String> {
+    let Some(object) = schema.as_object() else {
+        return Ok(JSON_VALUE.to_owned());
+    };
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return convert_ref(reference, root, visited_refs, format_adjustment);
+    }
+    if let Some(Value::Array(variants)) = object.get("anyOf").or_else(|| object.get("oneOf")) {
+        return convert_variants(variants, root, visited_refs, format_adjustment);
+    }
+    if let Some(Value::Array(schemas)) = object.get("allOf") {
+        if let [single] = schemas.as_slice() {
+            return convert_schema(single, root, visited_refs, format_adjustment);
+        }
+        return Ok(JSON_VALUE.to_owned());
+    }
+    if object.contains_key("enum") || object.contains_key("const") {
+        return Ok(convert_constants(object));
+    }
+
+    match object.get("type") {
+        Some(Value::String(type_name)) => {
+            convert_type(type_name, object, root, visited_refs, format_adjustment)
+        }
+        Some(Value::Array(type_names)) => {
+            let non_null = type_names
+                .iter()
+                .filter_map(Value::as_str)
+                .filter(|type_name| *type_name != "null")
+                .collect::<Vec<_>>();
+            match non_null.as_slice() {
+                [type_name] if non_null.len() < type_names.len() => Ok(format_adjustment(format!(
+                    "Maybe {}",
+                    convert_type(
+                        type_name,
+                        object,
+                        root,
+                        visited_refs,
+                        wrap_in_round_brackets
+                    )?
+                ))),
+                [type_name] => {
+                    convert_type(type_name, object, root, visited_refs, format_adjustment)
+                }
+                _ => Ok(JSON_VALUE.to_owned()),
+            }
+        }
+        _ => Ok(JSON_VALUE.to_owned()),
+    }
+}
+
+fn convert_ref<'a>(
+    reference: &'a str,
+    root: &'a Value,
+    visited_refs: &mut Vec<&'a str>,
+    format_adjustment: impl Fn(String) -> String,
+) -> Result<String> {
+    // Recursive types cannot be expressed as (structural) Elm records
+    if visited_refs.contains(&reference) {
+        return Ok(JSON_VALUE.to_owned());
+    }
+    let Some(referenced_schema) = reference
+        .strip_prefix('#')
+        .and_then(|pointer| root.pointer(pointer))
+    else {
+        return Ok(JSON_VALUE.to_owned());
+    };
+    visited_refs.push(reference);
+    let elm_type = convert_schema(referenced_schema, root, visited_refs, format_adjustment);
+    visited_refs.pop();
+    elm_type
+}
+
+fn convert_variants<'a>(
+    variants: &'a [Value],
+    root: &'a Value,
+    visited_refs: &mut Vec<&'a str>,
+    format_adjustment: impl Fn(String) -> String,
+) -> Result<String> {
+    let non_null = variants
+        .iter()
+        .filter(|variant| variant.get("type").and_then(Value::as_str) != Some("null"))
+        .collect::<Vec<_>>();
+    let Some((first, rest)) = non_null.split_first() else {
+        return Ok("()".to_owned());
+    };
+
+    // Variants with different shapes (e.g. untagged or tagged enums) have no structural Elm counterpart
+    let first_type = convert_schema(first, root, visited_refs, identity)?;
+    for variant in rest {
+        if convert_schema(variant, root, visited_refs, identity)? != first_type {
+            return Ok(JSON_VALUE.to_owned());
+        }
+    }
+
+    if non_null.len() < variants.len() {
+        Ok(format_adjustment(format!(
+            "Maybe {}",
+            convert_schema(first, root, visited_refs, wrap_in_round_brackets)?
+        )))
+    } else {
+        convert_schema(first, root, visited_refs, format_adjustment)
+    }
+}
+
+fn convert_constants(object: &Map<String, Value>) -> String {
+    let constants = match (object.get("enum"), object.get("const")) {
+        (Some(Value::Array(constants)), _) => constants.iter().collect::<Vec<_>>(),
+        (_, Some(constant)) => vec![constant],
+        _ => Vec::new(),
+    };
+    if constants.iter().all(|constant| constant.is_string()) {
+        "String".to_owned()
+    } else {
+        JSON_VALUE.to_owned()
+    }
+}
+
+fn convert_type<'a>(
+    type_name: &str,
+    object: &'a Map<String, Value>,
+    root: &'a Value,
+    visited_refs: &mut Vec<&'a str>,
+    format_adjustment: impl Fn(String) -> String,
+) -> Result<String> {
+    Ok(match type_name {
+        "null" => "()".to_owned(),
+        "boolean" => "Bool".to_owned(),
+        "integer" => "Int".to_owned(),
+        "number" => "Float".to_owned(),
+        "string" => "String".to_owned(),
+        "array" => {
+            if let Some(Value::Array(prefix_items)) = object.get("prefixItems") {
+                let types = prefix_items
+                    .iter()
+                    .map(|item| convert_schema(item, root, visited_refs, identity))
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", ");
+                return Ok(format!("( {types} )"));
+            }
+            let inner = match object.get("items") {
+                Some(items) => convert_schema(items, root, visited_refs, wrap_in_round_brackets)?,
+                None => JSON_VALUE.to_owned(),
+            };
+            format_adjustment(format!("List {inner}"))
+        }
+        "object" => match (object.get("properties"), object.get("additionalProperties")) {
+            (Some(Value::Object(properties)), _) => {
+                let fields = properties
+                    .iter()
+                    .map(|(name, property)| {
+                        Ok(format!(
+                            "{} : {}",
+                            field_name(name)?,
+                            convert_schema(property, root, visited_refs, identity)?
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+                    .join(", ");
+                format!("{{ {fields} }}")
+            }
+            (_, Some(value @ Value::Object(_))) => format_adjustment(format!(
+                "Dict String {}",
+                convert_schema(value, root, visited_refs, wrap_in_round_brackets)?
+            )),
+            _ => JSON_VALUE.to_owned(),
+        },
+        _ => JSON_VALUE.to_owned(),
+    })
+}
+
+/// Since Elm ports convert records field by field, a property has to be usable as an Elm record field as is.
+fn field_name(name: &str) -> Result<&str> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|first| first.is_lowercase())
+        && chars.all(|char| char.is_alphanumeric() || char == '_')
+        && !RESERVED_WORDS.contains(&name);
+    if valid {
+        Ok(name)
+    } else {
+        Err(Box::new(Error::InvalidFieldName(name.to_owned())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, convert::identity};
+
+    use schemars::JsonSchema;
+
+    use crate::{elm_type::wrap_in_round_brackets, Error};
+
+    use super::convert;
+
+    #[test]
+    fn simple_struct() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Test {
+            a: i64,
+            b: bool,
+        }
+        assert_eq!(convert::<Test>(identity).unwrap(), "{ a : Int, b : Bool }");
+    }
+
+    #[test]
+    fn simple_tuple() {
+        assert_eq!(
+            convert::<(i16, String)>(identity).unwrap(),
+            "( Int, String )"
+        );
+    }
+
+    #[test]
+    fn format_adjustment_option() {
+        assert_eq!(
+            convert::<Option<Vec<u8>>>(wrap_in_round_brackets).unwrap(),
+            "(Maybe (List Int))"
+        );
+    }
+
+    #[test]
+    fn simple_map() {
+        assert_eq!(
+            convert::<HashMap<String, u16>>(identity).unwrap(),
+            "Dict String Int"
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Inner {
+            b: String,
+        }
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Test {
+            a: Option<Inner>,
+            #[serde(flatten)]
+            inner: Inner,
+        }
+        assert_eq!(
+            convert::<Test>(identity).unwrap(),
+            "{ a : Maybe { b : String }, b : String }"
+        );
+    }
+
+    #[test]
+    fn unit_enum() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        enum Test {
+            A,
+            B,
+        }
+        assert_eq!(convert::<Vec<Test>>(identity).unwrap(), "List String");
+    }
+
+    #[test]
+    fn untagged_enum() {
+        #[derive(JsonSchema)]
+        #[serde(untagged)]
+        #[allow(dead_code)]
+        enum Test {
+            Id(u32),
+            Name(String),
+        }
+        assert_eq!(convert::<Test>(identity).unwrap(), "Json.Decode.Value");
+    }
+
+    #[test]
+    fn recursive_struct() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Test {
+            next: Option<Box<Test>>,
+        }
+        assert_eq!(
+            convert::<Test>(identity).unwrap(),
+            "{ next : Maybe Json.Decode.Value }"
+        );
+    }
+
+    #[test]
+    fn invalid_field_names() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Renamed {
+            #[serde(rename = "first-name")]
+            first_name: String,
+        }
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Keyword {
+            r#type: String,
+        }
+        let error = convert::<Renamed>(identity).unwrap_err();
+        assert!(matches!(*error, Error::InvalidFieldName(name) if name == "first-name"));
+        let error = convert::<Keyword>(identity).unwrap_err();
+        assert!(matches!(*error, Error::InvalidFieldName(name) if name == "type"));
+    }
+}
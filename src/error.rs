@@ -13,6 +13,8 @@ pub enum Error {
     SerdeJson(serde_json::Error),
     // Could not infer elm types based on given rust input/output types.
     TypeAnalysisError(serde_reflection::Error),
+    // A traced Rust type has no inferred Elm counterpart, e.g. an enum without a derived `ElmType`.
+    UnsupportedType(String),
     // Failed to read/write/delete files.
     DiskIOError {
         path: PathBuf,
@@ -20,6 +22,36 @@ pub enum Error {
     },
     // The qualified function name had the wrong format, or the Elm code did not compile.
     InvalidElmCall(String),
+    // An Elm decoder failed. Contains the message of `Json.Decode.errorToString`.
+    ElmDecodeError(String),
+    // A field of a Rust type cannot be used as an Elm record field (e.g. `first-name` or `type`).
+    InvalidFieldName(String),
+    // `Test.Runner.fuzz` rejected the fuzzer.
+    InvalidFuzzer(String),
+    // `Url.fromString` could not parse the given url.
+    InvalidUrl(String),
+    // The Elm program has no incoming (or outgoing) port with the given name.
+    UnknownPort(String),
+    // The Http service failed to answer a request of the Elm code (or there was none).
+    HttpServiceError(String),
+    // There is no tokio runtime the futures of the Rust side (e.g. of the Http service) can run on.
+    #[cfg(feature = "v8")]
+    AsyncRuntimeError(String),
+    // A selector passed to `HtmlNode::find` could not be parsed.
+    InvalidSelector(String),
+    // An event could not be dispatched, since not exactly one element of the view matched the selector.
+    NoUniqueElement {
+        selector: String,
+        count: usize,
+    },
+    // A value did not survive the round trip through serde and the Elm decoder and encoder.
+    // Contains the Json of each stage that was reached.
+    CodecRoundtripError {
+        value: String,
+        serialized: serde_json::Value,
+        elm_encoded: Option<serde_json::Value>,
+        message: String,
+    },
 }
 
 /// A simple Result alias with the crate specific `Error` type.
@@ -36,12 +68,37 @@ impl Display for Error {
         match self {
             Error::RuntimeError(error) => error.fmt(f),
             Error::TypeAnalysisError(error) => error.fmt(f),
+            Error::UnsupportedType(message) => f.write_fmt(format_args!("Unsupported type: {message}")),
             Error::DiskIOError { path, source } => {
                                         f.write_fmt(format_args!("DiskIOError at {path:?}: {source}"))
                                     }
             Error::InvalidElmCall(function_name) => f.write_fmt(format_args!("Invalid Elm Call {function_name}. Expected format is MyModule.MySubmodule.myMethod.")),
             Error::SerdeJson(error) => error.fmt(f),
             Error::NonF64Number(number) => f.write_fmt(format_args!("Non f64 number {number}")),
+            Error::ElmDecodeError(error) => f.write_fmt(format_args!("Elm decoder failed: {error}")),
+            Error::InvalidFieldName(name) => f.write_fmt(format_args!("Invalid Elm record field name {name}")),
+            Error::InvalidFuzzer(error) => f.write_fmt(format_args!("Invalid fuzzer: {error}")),
+            Error::InvalidUrl(url) => f.write_fmt(format_args!("Invalid url {url}")),
+            Error::UnknownPort(port) => f.write_fmt(format_args!("Unknown port {port}")),
+            Error::HttpServiceError(error) => f.write_fmt(format_args!("Http service failed: {error}")),
+            #[cfg(feature = "v8")]
+            Error::AsyncRuntimeError(error) => f.write_fmt(format_args!("Async runtime error: {error}")),
+            Error::InvalidSelector(selector) => f.write_fmt(format_args!("Invalid selector {selector}")),
+            Error::NoUniqueElement { selector, count } => f.write_fmt(format_args!("Expected exactly one element matching {selector}, found {count}")),
+            Error::CodecRoundtripError {
+                value,
+                serialized,
+                elm_encoded,
+                message,
+            } => {
+                writeln!(f, "Codec round trip failed: {message}")?;
+                writeln!(f, "Rust value: {value}")?;
+                writeln!(f, "Serialized by serde: {serialized}")?;
+                match elm_encoded {
+                    Some(elm_encoded) => write!(f, "Encoded by Elm: {elm_encoded}"),
+                    None => write!(f, "Encoded by Elm: -"),
+                }
+            }
         }
     }
 }
@@ -0,0 +1,135 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::Result, json::FallibleOutput, random, ElmDependencies, ElmFunctionHandle, Error,
+    PortType,
+};
+
+/// The seed, the number of values to generate and an optional shrink limit.
+type FuzzerInput = (i32, usize, Option<usize>);
+
+/// A handle to an `elm-explorations/test` `Fuzzer`, created via `ElmRoot::prepare_fuzzer`.
+pub struct ElmFuzzerHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<FuzzerInput, FallibleOutput>,
+    pub(crate) _type: PhantomData<O>,
+}
+
+impl<O> ElmFuzzerHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Generates `n` values with the fuzzer, starting with `Random.initialSeed seed`.
+    /// The same seed always results in the same values.
+    #[cfg(feature = "v8")]
+    pub fn sample(&self, seed: i32, n: usize) -> Result<Vec<O>> {
+        let result = self.handle.call((seed, n, None))?;
+        fuzzer_output(result)
+    }
+
+    /// Generates `n` values with the fuzzer, starting with `Random.initialSeed seed`.
+    /// The same seed always results in the same values.
+    #[cfg(feature = "quickjs")]
+    pub async fn sample(&self, seed: i32, n: usize) -> Result<Vec<O>> {
+        let result = self.handle.call((seed, n, None)).await?;
+        fuzzer_output(result)
+    }
+
+    /// Returns (at most `limit`) shrunk candidates of the value at position `index` of `sample(seed, _)`,
+    /// e.g. to find a minimal input of a failing Rust property test.
+    #[cfg(feature = "v8")]
+    pub fn shrink(&self, seed: i32, index: usize, limit: usize) -> Result<Vec<O>> {
+        let result = self.handle.call((seed, index + 1, Some(limit)))?;
+        fuzzer_output(result)
+    }
+
+    /// Returns (at most `limit`) shrunk candidates of the value at position `index` of `sample(seed, _)`,
+    /// e.g. to find a minimal input of a failing Rust property test.
+    #[cfg(feature = "quickjs")]
+    pub async fn shrink(&self, seed: i32, index: usize, limit: usize) -> Result<Vec<O>> {
+        let result = self.handle.call((seed, index + 1, Some(limit))).await?;
+        fuzzer_output(result)
+    }
+}
+
+fn fuzzer_output<O: DeserializeOwned>(output: FallibleOutput) -> Result<Vec<O>> {
+    match output.into_result()? {
+        Ok(values) => Ok(serde_json::from_value(values)?),
+        Err(error) => Err(Box::new(Error::InvalidFuzzer(error))),
+    }
+}
+
+/// Wraps a fuzzer into a function taking the seed, the number of values to generate and an optional shrink limit.
+pub(crate) fn run_fuzzer(fuzzer: &str) -> String {
+    format!(
+        "\\seed count shrinkLimit -> Test.Runner.fuzz {fuzzer} |> Result.map (fuzzValues seed count shrinkLimit)"
+    )
+}
+
+/// The seed, the number of values to generate and an optional shrink limit,
+/// along with the declarations needed by `run_fuzzer`.
+pub(crate) fn fuzzer_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Random");
+    dependencies.import("Test.Runner");
+    random::declare_generate_values(dependencies);
+    if dependencies.declare("fuzzValues") {
+        dependencies.define("fuzzValues", FUZZ_VALUES.to_owned());
+        dependencies.define("shrinkCandidates", SHRINK_CANDIDATES.to_owned());
+    }
+    Ok(vec![
+        PortType::Native("Int".to_owned()),
+        PortType::Native("Int".to_owned()),
+        PortType::Native("Maybe Int".to_owned()),
+    ])
+}
+
+/// Generates `count` values, or the shrunk candidates of the last one if a shrink limit is given.
+const FUZZ_VALUES: &str = "fuzzValues : Int -> Int -> Maybe Int -> Random.Generator ( a, Test.Runner.Shrinkable a ) -> List a
+fuzzValues seed count shrinkLimit generator =
+    let
+        values =
+            generateValues count generator (Random.initialSeed seed) []
+    in
+    case shrinkLimit of
+        Nothing ->
+            List.map Tuple.first values
+
+        Just limit ->
+            values
+                |> List.drop (count - 1)
+                |> List.head
+                |> Maybe.map (Tuple.second >> shrinkCandidates limit)
+                |> Maybe.withDefault []";
+
+const SHRINK_CANDIDATES: &str = "shrinkCandidates : Int -> Test.Runner.Shrinkable a -> List a
+shrinkCandidates limit shrinkable =
+    if limit <= 0 then
+        []
+
+    else
+        case Test.Runner.shrink False shrinkable of
+            Just ( candidate, next ) ->
+                candidate :: shrinkCandidates (limit - 1) next
+
+            Nothing ->
+                []";
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::fuzzer_output;
+    use crate::{json::FallibleOutput, Error};
+
+    #[test]
+    fn invalid_fuzzer() {
+        let error =
+            fuzzer_output::<u8>(FallibleOutput::failed(json!("Invalid fuzzer"))).unwrap_err();
+        assert!(matches!(*error, Error::InvalidFuzzer(_)));
+        assert_eq!(
+            fuzzer_output::<u8>(FallibleOutput::succeeded(json!([1, 2]))).unwrap(),
+            [1, 2]
+        );
+    }
+}
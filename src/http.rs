@@ -0,0 +1,271 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tower_service::Service;
+
+use crate::error::{Error, Result};
+#[cfg(feature = "v8")]
+use crate::v8::TokioRuntime;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+type ResponseFuture = Pin<Box<dyn Future<Output = Result<http::Response<Bytes>>> + Send>>;
+
+/// The `tower::Service` answering the Http requests of the Elm code, see `ElmRoot::http_service`.
+#[derive(Clone)]
+pub(crate) struct HttpService(
+    Arc<dyn Fn(http::Request<Full<Bytes>>) -> ResponseFuture + Send + Sync>,
+);
+
+impl HttpService {
+    pub fn new<S, B>(service: S) -> Self
+    where
+        S: Service<http::Request<Full<Bytes>>, Response = http::Response<B>>
+            + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send,
+        S::Error: Into<BoxError>,
+        B: http_body::Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<BoxError>,
+    {
+        Self(Arc::new(move |request| {
+            let mut service = service.clone();
+            Box::pin(async move {
+                std::future::poll_fn(|cx| service.poll_ready(cx))
+                    .await
+                    .map_err(service_error)?;
+                let response = service.call(request).await.map_err(service_error)?;
+                let (parts, body) = response.into_parts();
+                let body = body.collect().await.map_err(service_error)?.to_bytes();
+                Ok(http::Response::from_parts(parts, body))
+            })
+        }))
+    }
+
+    /// Answers the requests one after another.
+    async fn serve(&self, requests: Vec<HttpRequest>) -> Result<Vec<HttpResponse>> {
+        let mut responses = Vec::with_capacity(requests.len());
+        for request in requests {
+            let id = request.id;
+            let url = request.url.clone();
+            let arraybuffer = request.response_type == "arraybuffer";
+            let response = (self.0)(request.into_http()?).await?;
+            responses.push(HttpResponse::new(id, url, response, arraybuffer));
+        }
+        Ok(responses)
+    }
+}
+
+fn service_error(error: impl Into<BoxError>) -> Box<Error> {
+    Box::new(Error::HttpServiceError(error.into().to_string()))
+}
+
+/// Answers the Http requests of the Elm code with the given service.
+/// Since the v8 API is synchronous, the service runs on the tokio runtime of the `ElmRoot`.
+#[cfg(feature = "v8")]
+pub(crate) fn serve(
+    service: Option<&HttpService>,
+    tokio: &TokioRuntime,
+    requests: Vec<HttpRequest>,
+) -> Result<Vec<HttpResponse>> {
+    let Some(service) = service else {
+        return Err(no_service(&requests));
+    };
+    tokio.block_on(service.serve(requests))?
+}
+
+/// Answers the Http requests of the Elm code with the given service.
+#[cfg(feature = "quickjs")]
+pub(crate) async fn serve(
+    service: Option<&HttpService>,
+    requests: Vec<HttpRequest>,
+) -> Result<Vec<HttpResponse>> {
+    let Some(service) = service else {
+        return Err(no_service(&requests));
+    };
+    service.serve(requests).await
+}
+
+/// The state of a binding call: Either it produced its output,
+/// or it waits for the answers to its Http requests before it can continue.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub(crate) enum CallStep {
+    Pending {
+        pending: u64,
+        requests: Vec<HttpRequest>,
+    },
+    Done {
+        #[serde(default)]
+        output: Value,
+    },
+}
+
+fn no_service(requests: &[HttpRequest]) -> Box<Error> {
+    let urls = requests
+        .iter()
+        .map(|request| format!("{} {}", request.method, request.url))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Box::new(Error::HttpServiceError(format!(
+        "No Http service to answer {urls}, see `ElmRoot::http_service`"
+    )))
+}
+
+/// A request the Elm code sent via `XMLHttpRequest`.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HttpRequest {
+    id: u64,
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<RequestBody>,
+    response_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum RequestBody {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl HttpRequest {
+    fn into_http(self) -> Result<http::Request<Full<Bytes>>> {
+        let body = match self.body {
+            None => Bytes::new(),
+            Some(RequestBody::Text(text)) => Bytes::from(text),
+            Some(RequestBody::Bytes(bytes)) => Bytes::from(bytes),
+        };
+        let mut builder = http::Request::builder()
+            .method(self.method.as_str())
+            .uri(self.url.as_str());
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(Full::new(body)).map_err(|error| {
+            Box::new(Error::HttpServiceError(format!(
+                "Invalid request {} {}: {error}",
+                self.method, self.url
+            )))
+        })
+    }
+}
+
+/// The answer to an `HttpRequest`, in the shape of the `XMLHttpRequest` fields Elm reads.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct HttpResponse {
+    id: u64,
+    status: u16,
+    status_text: String,
+    url: String,
+    /// All headers in the format of `XMLHttpRequest.getAllResponseHeaders`
+    headers: String,
+    /// A string, or the list of bytes for `Http.expectBytes`
+    body: Value,
+}
+
+impl HttpResponse {
+    fn new(id: u64, url: String, response: http::Response<Bytes>, arraybuffer: bool) -> Self {
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                format!("{name}: {}\r\n", String::from_utf8_lossy(value.as_bytes()))
+            })
+            .collect();
+        let body = if arraybuffer {
+            Value::from(response.body().to_vec())
+        } else {
+            Value::from(String::from_utf8_lossy(response.body()))
+        };
+        Self {
+            id,
+            status: response.status().as_u16(),
+            status_text: response
+                .status()
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_owned(),
+            url,
+            headers,
+            body,
+        }
+    }
+}
+
+/// Prepends the `XMLHttpRequest` shim to the ESM compatible binding.
+pub(crate) fn with_http_shim(esm_compiled_binding: &str) -> String {
+    format!("{HTTP_JS}{esm_compiled_binding}")
+}
+
+const HTTP_JS: &str = include_str!("./templates/http.js");
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{HttpRequest, HttpResponse};
+    use crate::Error;
+
+    #[test]
+    fn request_from_elm() {
+        let request: HttpRequest = serde_json::from_value(json!({
+            "id": 1,
+            "method": "POST",
+            "url": "/users?page=2",
+            "headers": [["Content-Type", "application/json"]],
+            "body": "{\"name\":\"Alice\"}",
+            "responseType": ""
+        }))
+        .unwrap();
+        let request = request.into_http().unwrap();
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.uri().path(), "/users");
+        assert_eq!(request.headers()["content-type"], "application/json");
+    }
+
+    #[test]
+    fn invalid_request_from_elm() {
+        let request: HttpRequest = serde_json::from_value(json!({
+            "id": 1,
+            "method": "GET",
+            "url": "/users",
+            "headers": [["Invalid Header", "value"]],
+            "body": null,
+            "responseType": ""
+        }))
+        .unwrap();
+        let error = request.into_http().unwrap_err();
+        assert!(
+            matches!(*error, Error::HttpServiceError(message) if message.starts_with("Invalid request GET /users"))
+        );
+    }
+
+    #[test]
+    fn response_to_elm() {
+        let response = http::Response::builder()
+            .status(404)
+            .header("content-type", "text/plain")
+            .body("missing".into())
+            .unwrap();
+        assert_eq!(
+            HttpResponse::new(1, "/users/1".to_owned(), response, false),
+            HttpResponse {
+                id: 1,
+                status: 404,
+                status_text: "Not Found".to_owned(),
+                url: "/users/1".to_owned(),
+                headers: "content-type: text/plain\r\n".to_owned(),
+                body: json!("missing"),
+            }
+        );
+    }
+}
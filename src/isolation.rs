@@ -0,0 +1,344 @@
+use std::{
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// Directories within source directories which are not copied into an isolated project,
+/// since they are not needed for compilation.
+const IGNORED_DIRECTORIES: &[&str] = &["elm-stuff", "node_modules"];
+
+/// A temporary copy of the Elm project containing an `ElmRoot`,
+/// in which the given modules expose all of their values.
+/// Optionally, the test dependencies and the `tests` directory are made available to `elm make`.
+pub struct IsolatedProject {
+    project_dir: PathBuf,
+    root_path: PathBuf,
+}
+
+impl IsolatedProject {
+    pub fn create(
+        root_path: &Path,
+        seed: u128,
+        exposed_modules: &[String],
+        test_directory: Option<&str>,
+    ) -> Result<Self> {
+        let root_path = root_path
+            .canonicalize()
+            .map_err(Error::map_disk_error(root_path.to_owned()))?;
+        let original_project_dir = &project_dir(&root_path)?;
+        let project_dir = std::env::temp_dir().join(format!("elm-rust-binding-{seed}"));
+        let project = Self {
+            root_path: project_dir.join(
+                root_path
+                    .strip_prefix(original_project_dir)
+                    .unwrap_or(Path::new("")),
+            ),
+            project_dir,
+        };
+        if let Err(error) = project.copy(original_project_dir, exposed_modules, test_directory) {
+            // Do not leave a partial copy behind, the error of the copy is more relevant
+            let _ = fs::remove_dir_all(&project.project_dir);
+            return Err(error);
+        }
+        Ok(project)
+    }
+
+    /// The copy of the `ElmRoot` directory
+    pub fn root_path(&self) -> &Path {
+        &self.root_path
+    }
+
+    pub fn remove(self) -> Result<()> {
+        fs::remove_dir_all(&self.project_dir).map_err(Error::map_disk_error(self.project_dir))?;
+        Ok(())
+    }
+
+    /// Copies the elm.json and the source directories of the original project
+    /// (but nothing else, e.g. the `target` directory of a crate next to it).
+    fn copy(
+        &self,
+        original_project_dir: &Path,
+        exposed_modules: &[String],
+        test_directory: Option<&str>,
+    ) -> Result<()> {
+        fs::create_dir_all(&self.root_path)
+            .map_err(Error::map_disk_error(self.root_path.clone()))?;
+        let source_directories = self.copy_elm_json(original_project_dir, test_directory)?;
+        for module in exposed_modules {
+            let module_path = module.replace('.', "/") + ".elm";
+            let Some(module_file) = source_directories
+                .iter()
+                .map(|dir| dir.join(&module_path))
+                .find(|file| file.is_file())
+            else {
+                // Package modules (or modules outside of the project) are left untouched
+                continue;
+            };
+            let source = fs::read_to_string(&module_file)
+                .map_err(Error::map_disk_error(module_file.clone()))?;
+            if let Some(exposing_everything) = expose_all(&source) {
+                fs::write(&module_file, exposing_everything)
+                    .map_err(Error::map_disk_error(module_file))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Source directories outside of the original project directory are not copied,
+    /// so they are referenced by absolute paths instead. Returns the (copied) source directories.
+    fn copy_elm_json(
+        &self,
+        original_project_dir: &Path,
+        test_directory: Option<&str>,
+    ) -> Result<Vec<PathBuf>> {
+        let original_elm_json_path = original_project_dir.join("elm.json");
+        let elm_json = fs::read_to_string(&original_elm_json_path)
+            .map_err(Error::map_disk_error(original_elm_json_path))?;
+        let mut elm_json: Value = serde_json::from_str(&elm_json)?;
+        if let Some(test_directory) = test_directory {
+            let test_directory_exists = original_project_dir.join(test_directory).is_dir();
+            include_tests(&mut elm_json, test_directory, test_directory_exists);
+        }
+
+        let mut copied_source_directories = Vec::new();
+        if let Some(Value::Array(source_directories)) = elm_json.get_mut("source-directories") {
+            for source_directory in source_directories.iter_mut() {
+                let Some(dir) = source_directory.as_str() else {
+                    continue;
+                };
+                let copied_dir = self.project_dir.join(dir);
+                match original_project_dir.join(dir).canonicalize() {
+                    Ok(original_dir) if !original_dir.starts_with(original_project_dir) => {
+                        *source_directory =
+                            Value::String(original_dir.to_string_lossy().into_owned());
+                    }
+                    Ok(original_dir) => {
+                        copy_dir(&original_dir, &copied_dir)?;
+                        copied_source_directories.push(copied_dir);
+                    }
+                    // Missing source directories are reported by the compiler
+                    Err(_) => copied_source_directories.push(copied_dir),
+                }
+            }
+        }
+        let elm_json_path = self.project_dir.join("elm.json");
+        fs::write(&elm_json_path, serde_json::to_string_pretty(&elm_json)?)
+            .map_err(Error::map_disk_error(elm_json_path))?;
+        Ok(copied_source_directories)
+    }
+}
+
+/// The directory containing the elm.json of the project the given directory belongs to.
+pub fn project_dir(dir: &Path) -> Result<PathBuf> {
+    let dir = dir
+        .canonicalize()
+        .map_err(Error::map_disk_error(dir.to_owned()))?;
+    let Some(project_dir) = dir.ancestors().find(|dir| dir.join("elm.json").is_file()) else {
+        return Err(Box::new(Error::InvalidElmCall(format!(
+            "No elm.json found in {dir:?} or its parent directories"
+        ))));
+    };
+    Ok(project_dir.to_owned())
+}
+
+/// Promotes the test dependencies of an application to regular dependencies
+/// and adds the test directory to the source directories.
+fn include_tests(elm_json: &mut Value, test_directory: &str, test_directory_exists: bool) {
+    let test_dependencies = elm_json
+        .get_mut("test-dependencies")
+        .map(Value::take)
+        .unwrap_or_default();
+    if let Some(Value::Object(dependencies)) = elm_json.get_mut("dependencies") {
+        if let Some(Value::Object(test_direct)) = test_dependencies.get("direct") {
+            for (package, version) in test_direct {
+                if let Some(Value::Object(indirect)) = dependencies.get_mut("indirect") {
+                    indirect.remove(package);
+                }
+                if let Some(Value::Object(direct)) = dependencies.get_mut("direct") {
+                    direct.entry(package).or_insert_with(|| version.clone());
+                }
+            }
+        }
+        if let Some(Value::Object(test_indirect)) = test_dependencies.get("indirect") {
+            for (package, version) in test_indirect {
+                let is_direct = matches!(dependencies.get("direct"), Some(Value::Object(direct)) if direct.contains_key(package));
+                if let (false, Some(Value::Object(indirect))) =
+                    (is_direct, dependencies.get_mut("indirect"))
+                {
+                    indirect.entry(package).or_insert_with(|| version.clone());
+                }
+            }
+        }
+    }
+    elm_json["test-dependencies"] = serde_json::json!({ "direct": {}, "indirect": {} });
+
+    if let (true, Some(Value::Array(source_directories))) = (
+        test_directory_exists,
+        elm_json.get_mut("source-directories"),
+    ) {
+        let test_directory = test_directory.trim_end_matches('/');
+        if !source_directories
+            .iter()
+            .any(|dir| dir.as_str().map(|dir| dir.trim_end_matches('/')) == Some(test_directory))
+        {
+            source_directories.push(Value::String(test_directory.to_owned()));
+        }
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).map_err(Error::map_disk_error(to.to_owned()))?;
+    for entry in fs::read_dir(from).map_err(Error::map_disk_error(from.to_owned()))? {
+        let entry = entry.map_err(Error::map_disk_error(from.to_owned()))?;
+        let path = entry.path();
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if path.is_dir() {
+            if file_name.starts_with('.') || IGNORED_DIRECTORIES.contains(&file_name.as_ref()) {
+                continue;
+            }
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name())).map_err(Error::map_disk_error(path))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the exposing list in the module header with `exposing (..)`.
+/// Returns `None` if no module header was found.
+fn expose_all(source: &str) -> Option<String> {
+    let (_, exposing_list) = module_header(source)?;
+    Some(format!(
+        "{}(..){}",
+        &source[..exposing_list.start],
+        &source[exposing_list.end..]
+    ))
+}
+
+/// The module name and the range of the exposing list (including the parentheses) of an Elm module.
+pub fn module_header(source: &str) -> Option<(&str, Range<usize>)> {
+    let mut offset = 0;
+    let header_start = source.split_inclusive('\n').find_map(|line| {
+        let line_start = offset;
+        offset += line.len();
+        ["module ", "port module ", "effect module "]
+            .iter()
+            .any(|keyword| line.starts_with(keyword))
+            .then_some(line_start)
+    })?;
+    let module_name = source[header_start..]
+        .split_whitespace()
+        .skip_while(|word| *word != "module")
+        .nth(1)?;
+    let exposing = header_start + source[header_start..].find("exposing")?;
+    let list_start = exposing + source[exposing..].find('(')?;
+
+    let mut depth = 0;
+    for (index, char) in source[list_start..].char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some((module_name, list_start..list_start + index + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::{expose_all, include_tests, IsolatedProject};
+
+    #[test]
+    fn simple_header() {
+        assert_eq!(
+            expose_all("module Math exposing (square)\n\nsquare n = n * n\n").unwrap(),
+            "module Math exposing (..)\n\nsquare n = n * n\n"
+        );
+    }
+
+    #[test]
+    fn multiline_header_with_types() {
+        assert_eq!(
+            expose_all("port module Api.User exposing\n    ( User(..)\n    , decoder\n    )\n\nimport Json.Decode\n")
+                .unwrap(),
+            "port module Api.User exposing\n    (..)\n\nimport Json.Decode\n"
+        );
+    }
+
+    #[test]
+    fn leading_comment() {
+        assert_eq!(
+            expose_all("-- exposing (nothing)\nmodule A exposing (a)\n").unwrap(),
+            "-- exposing (nothing)\nmodule A exposing (..)\n"
+        );
+    }
+
+    #[test]
+    fn no_header() {
+        assert_eq!(expose_all("a = 1\n"), None);
+    }
+
+    #[test]
+    fn isolated_copy() {
+        let project = IsolatedProject::create(
+            Path::new("./tests/elm/src"),
+            Uuid::now_v7().as_u128(),
+            &["Private".to_owned()],
+            None,
+        )
+        .unwrap();
+        let copied_module = fs::read_to_string(project.root_path().join("Private.elm")).unwrap();
+        let original_module = fs::read_to_string("./tests/elm/src/Private.elm").unwrap();
+        // Only the source directories are copied
+        let copied_tests = project.root_path().join("../tests").exists();
+        project.remove().unwrap();
+        assert!(!copied_tests);
+        assert!(copied_module.starts_with("module Private exposing (..)"));
+        assert!(original_module.starts_with("module Private exposing (double)"));
+    }
+
+    #[test]
+    fn test_dependencies_promoted() {
+        let mut elm_json = json!({
+            "source-directories": ["src"],
+            "dependencies": {
+                "direct": { "elm/core": "1.0.5" },
+                "indirect": { "elm/random": "1.0.0" }
+            },
+            "test-dependencies": {
+                "direct": { "elm/random": "1.0.0", "elm-explorations/test": "1.2.2" },
+                "indirect": { "elm/bytes": "1.0.8" }
+            }
+        });
+        include_tests(&mut elm_json, "tests/", true);
+        assert_eq!(
+            elm_json,
+            json!({
+                "source-directories": ["src", "tests"],
+                "dependencies": {
+                    "direct": {
+                        "elm/core": "1.0.5",
+                        "elm/random": "1.0.0",
+                        "elm-explorations/test": "1.2.2"
+                    },
+                    "indirect": { "elm/bytes": "1.0.8" }
+                },
+                "test-dependencies": { "direct": {}, "indirect": {} }
+            })
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{error::Result, ElmFunctionHandle, Error};
+
+/// Json input of an Elm decoder, either as a raw Json string or as an already parsed `serde_json::Value`.
+pub trait IntoJson {
+    fn into_json(self) -> Result<Value>;
+}
+
+impl IntoJson for Value {
+    fn into_json(self) -> Result<Value> {
+        Ok(self)
+    }
+}
+
+impl IntoJson for &str {
+    fn into_json(self) -> Result<Value> {
+        Ok(serde_json::from_str(self)?)
+    }
+}
+
+impl IntoJson for String {
+    fn into_json(self) -> Result<Value> {
+        self.as_str().into_json()
+    }
+}
+
+/// A handle to an Elm `Json.Decode.Decoder`, created via `ElmRoot::prepare_decoder`.
+/// Calling it runs `Json.Decode.decodeValue` in Elm and returns the decoded value.
+pub struct ElmDecoderHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<Value, FallibleOutput>,
+    pub(crate) _type: PhantomData<O>,
+}
+
+impl<O> ElmDecoderHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Decodes the given Json with the Elm decoder.
+    /// Fails with `Error::ElmDecodeError` if the Elm decoder fails.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, json: impl IntoJson) -> Result<O> {
+        let result = self.handle.call(json.into_json()?)?;
+        fallible_output(result)
+    }
+
+    /// Decodes the given Json with the Elm decoder.
+    /// Fails with `Error::ElmDecodeError` if the Elm decoder fails.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, json: impl IntoJson) -> Result<O> {
+        let result = self.handle.call(json.into_json()?).await?;
+        fallible_output(result)
+    }
+}
+
+/// A handle to an Elm Json encoder, created via `ElmRoot::prepare_encoder`.
+/// Calling it converts the input into the Elm value and runs the encoder.
+pub struct ElmEncoderHandle<I> {
+    pub(crate) handle: ElmFunctionHandle<I, String>,
+}
+
+impl<I> ElmEncoderHandle<I>
+where
+    I: Serialize,
+{
+    /// Encodes the given value with the Elm encoder.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, input: I) -> Result<Value> {
+        Ok(serde_json::from_str(&self.call_to_string(input)?)?)
+    }
+
+    /// Encodes the given value with the Elm encoder.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, input: I) -> Result<Value> {
+        Ok(serde_json::from_str(&self.call_to_string(input).await?)?)
+    }
+
+    /// Encodes the given value with the Elm encoder and returns the Json exactly as `Json.Encode.encode 0` renders it,
+    /// e.g. to compare the order of the fields or the formatting of numbers with the Json of your backend.
+    #[cfg(feature = "v8")]
+    pub fn call_to_string(&self, input: I) -> Result<String> {
+        self.handle.call(input)
+    }
+
+    /// Encodes the given value with the Elm encoder and returns the Json exactly as `Json.Encode.encode 0` renders it,
+    /// e.g. to compare the order of the fields or the formatting of numbers with the Json of your backend.
+    #[cfg(feature = "quickjs")]
+    pub async fn call_to_string(&self, input: I) -> Result<String> {
+        self.handle.call(input).await
+    }
+}
+
+/// The output of a fallible binding, which is sent as `{ ok : Bool, error : Maybe x, value : Maybe a }`.
+/// The flag is needed since the error or the value might be encoded as `null` themselves, e.g. for `()`.
+#[derive(Deserialize, Debug)]
+pub(crate) struct FallibleOutput {
+    ok: bool,
+    error: Value,
+    value: Value,
+}
+
+impl FallibleOutput {
+    /// The value, or the error deserialized into `X`.
+    pub(crate) fn into_result<X: DeserializeOwned>(self) -> Result<std::result::Result<Value, X>> {
+        if self.ok {
+            Ok(Ok(self.value))
+        } else {
+            Ok(Err(serde_json::from_value(self.error)?))
+        }
+    }
+}
+
+#[cfg(test)]
+impl FallibleOutput {
+    pub(crate) fn succeeded(value: Value) -> Self {
+        Self {
+            ok: true,
+            error: Value::Null,
+            value,
+        }
+    }
+
+    pub(crate) fn failed(error: Value) -> Self {
+        Self {
+            ok: false,
+            error,
+            value: Value::Null,
+        }
+    }
+}
+
+/// Converts the output of a fallible binding failing with the message of `Json.Decode.errorToString`.
+pub(crate) fn fallible_output<O: DeserializeOwned>(output: FallibleOutput) -> Result<O> {
+    match output.into_result()? {
+        Ok(value) => Ok(serde_json::from_value(value)?),
+        Err(error) => Err(Box::new(Error::ElmDecodeError(error))),
+    }
+}
+
+/// Checks the output of a round trip binding (decoding and re-encoding the serialized value in Elm).
+pub(crate) fn check_roundtrip<T>(value: &T, serialized: Value, output: FallibleOutput) -> Result<()>
+where
+    T: DeserializeOwned + PartialEq + Debug,
+{
+    let failure = |elm_encoded, message| {
+        Err(Box::new(Error::CodecRoundtripError {
+            value: format!("{value:?}"),
+            serialized: serialized.clone(),
+            elm_encoded,
+            message,
+        }))
+    };
+    let elm_encoded = match output.into_result::<String>()? {
+        Ok(elm_encoded) => elm_encoded,
+        Err(error) => return failure(None, format!("Elm decoder failed: {error}")),
+    };
+    match serde_json::from_value::<T>(elm_encoded.clone()) {
+        Ok(roundtripped) if &roundtripped == value => Ok(()),
+        Ok(roundtripped) => failure(
+            Some(elm_encoded),
+            format!("Value changed to {roundtripped:?}"),
+        ),
+        Err(error) => failure(
+            Some(elm_encoded),
+            format!("Deserializing the Elm encoded Json failed: {error}"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::{check_roundtrip, FallibleOutput};
+    use crate::Error;
+
+    #[derive(Deserialize, PartialEq, Debug)]
+    struct User {
+        name: String,
+    }
+
+    #[test]
+    fn roundtrip_success() {
+        let user = User {
+            name: "Alice".to_owned(),
+        };
+        let json = json!({ "name": "Alice" });
+        assert!(check_roundtrip(&user, json.clone(), FallibleOutput::succeeded(json)).is_ok());
+    }
+
+    #[test]
+    fn roundtrip_changed_value() {
+        let user = User {
+            name: "Alice".to_owned(),
+        };
+        let error = check_roundtrip(
+            &user,
+            json!({ "name": "Alice" }),
+            FallibleOutput::succeeded(json!({ "name": "Bob" })),
+        )
+        .unwrap_err();
+        let Error::CodecRoundtripError {
+            elm_encoded,
+            message,
+            ..
+        } = *error
+        else {
+            panic!("Unexpected error {error}");
+        };
+        assert_eq!(elm_encoded, Some(json!({ "name": "Bob" })));
+        assert!(message.contains("Bob"));
+    }
+
+    #[test]
+    fn roundtrip_decoder_failure() {
+        let user = User {
+            name: "Alice".to_owned(),
+        };
+        let error = check_roundtrip(
+            &user,
+            json!({ "name": "Alice" }),
+            FallibleOutput::failed(json!("Expecting a field")),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            *error,
+            Error::CodecRoundtripError {
+                elm_encoded: None,
+                ..
+            }
+        ));
+    }
+}
@@ -3,10 +3,26 @@ compile_error!("Cannot enable both 'v8' and 'quickjs' features");
 #[cfg(not(any(feature = "v8", feature = "quickjs")))]
 compile_error!("Please enable one of the features: 'v8', 'quickjs'");
 
+mod application;
+mod bytes;
+mod clock;
+mod elm_test;
 mod elm_type;
 mod error;
+mod fuzz;
+mod http;
+mod isolation;
+mod json;
+mod parser;
+mod program;
+mod query;
 #[cfg(feature = "quickjs")]
 mod quickjs;
+mod random;
+mod task;
+mod url;
+mod view;
+mod worker;
 #[cfg(feature = "quickjs")]
 pub use quickjs::ElmFunctionHandle;
 
@@ -15,11 +31,53 @@ mod v8;
 #[cfg(feature = "v8")]
 pub use v8::ElmFunctionHandle;
 
-use std::{convert::identity, fs, path::PathBuf, process::Command};
+use std::{
+    convert::identity,
+    fmt::Debug,
+    fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
 
+pub use application::{ElmApplicationHandle, Navigation};
+pub use bytes::ElmBytesDecoderHandle;
+use clock::ClockSettings;
+#[cfg(feature = "derive")]
+pub use elm_rust_binding_derive::ElmType;
+pub use elm_test::{ElmTestMode, ElmTestReport, ElmTestResult};
+use elm_type::PortType;
+pub use elm_type::{ElmCompatible, ElmDependencies, ElmType};
 pub use error::{Error, Result};
-use serde::de::DeserializeOwned;
+pub use fuzz::ElmFuzzerHandle;
+use http::HttpService;
+use http_body_util::Full;
+use isolation::IsolatedProject;
+pub use json::{ElmDecoderHandle, ElmEncoderHandle, IntoJson};
+pub use parser::{DeadEnd, ElmParserHandle};
+use program::ProgramKind;
+pub use program::{ElmProgram, ElmProgramHandle, PortMessage};
+pub use query::Selection;
+pub use random::ElmGeneratorHandle;
+use serde::{de::DeserializeOwned, Serialize};
+pub use task::ElmTaskHandle;
+pub use url::ElmUrlParserHandle;
 use uuid::Uuid;
+pub use view::{ElmViewHandle, HtmlElement, HtmlNode};
+pub use worker::{ElmWorker, ElmWorkerHandle, PortSender};
+
+// Allows the derive macro to refer to this crate as `::elm_rust_binding` in our own tests
+extern crate self as elm_rust_binding;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::elm_type::codec::{Definition, Element, Field, Shape, Variant, VariantShape};
+    #[cfg(feature = "derive")]
+    pub use crate::elm_type::codec::{Registration, ELM_TYPES};
+    #[cfg(feature = "derive")]
+    pub use linkme;
+}
 
 /// The main entrypoint for this crate.
 ///
@@ -27,125 +85,1409 @@ use uuid::Uuid;
 pub struct ElmRoot {
     root_path: PathBuf,
     debug: bool,
+    expose_private: bool,
+    clock: ClockSettings,
+    http: Option<HttpService>,
+    #[cfg(feature = "v8")]
+    tokio: v8::TokioRuntime,
+}
+
+macro_rules! log {
+    ($self: expr, $($arg:tt)*) => {
+        if $self.debug {
+            println!($($arg)*)
+        }
+    };
 }
 
-macro_rules! log {
-    ($self: expr, $($arg:tt)*) => {
-        if $self.debug {
-            println!($($arg)*)
-        }
-    };
-}
+impl ElmRoot {
+    /// Create an `ElmRoot` for a given directory.
+    /// The directory should NOT be the one with the elm.json file in it,
+    /// but the directory with the .elm files in it (with the default elm.json file, this is usually ./src).
+    ///
+    /// The reason for this is: You may choose any source-directories in your elm.json you like,
+    /// so we would need to parse elm.json and check all directories for the specified module.
+    pub fn new<P>(path: P) -> Result<Self>
+    where
+        PathBuf: From<P>,
+    {
+        Ok(Self {
+            root_path: PathBuf::from(path),
+            debug: false,
+            expose_private: false,
+            clock: ClockSettings::default(),
+            http: None,
+            #[cfg(feature = "v8")]
+            tokio: v8::TokioRuntime::default(),
+        })
+    }
+
+    /// Set the `ElmRoot` to debug mode.
+    ///
+    /// This has two effects:
+    /// - Some println! logs to describe what the crate is doing
+    /// - The temporarily created files are not deleted
+    pub fn debug(self) -> Self {
+        Self {
+            debug: true,
+            ..self
+        }
+    }
+
+    /// Allow preparing functions and values which are not exposed by their module.
+    ///
+    /// Bindings are then compiled in a temporary copy of the Elm project (its elm.json and source directories,
+    /// in the system's temp directory), in which the modules of the called functions (or imported by expressions)
+    /// expose everything via `exposing (..)`. Your Elm code itself is left untouched. Since the project has to be copied and compiled from scratch
+    /// for every binding, this is noticeably slower.
+    pub fn expose_private(self) -> Self {
+        Self {
+            expose_private: true,
+            ..self
+        }
+    }
+
+    /// Set the point in time the virtual clock of every call (and program instance) starts at.
+    ///
+    /// The Elm code does not run with the real timers of the JS runtime, but with a virtual clock:
+    /// `Time.now` returns its current time and `Process.sleep` or `Time.every` only complete when it is advanced.
+    /// Calls advance it automatically until they produce their result, programs via `advance`.
+    /// Defaults to the Unix epoch.
+    pub fn start_time(self, since_epoch: Duration) -> Self {
+        Self {
+            clock: self.clock.with_start_time(since_epoch),
+            ..self
+        }
+    }
+
+    /// Seed the `Math.random` of the JS runtime, which is deterministic (with a seed of 0 by default).
+    pub fn random_seed(self, seed: u32) -> Self {
+        Self {
+            clock: ClockSettings { seed, ..self.clock },
+            ..self
+        }
+    }
+
+    /// Answer the Http requests of the Elm code (sent with `elm/http`) with the given `tower::Service`,
+    /// e.g. the `axum::Router` of your backend, instead of sending them over the network.
+    ///
+    /// Requests are answered as soon as they are sent, without any time passing on the virtual clock.
+    /// Without a service, Elm code sending a request fails with `Error::HttpServiceError`.
+    /// Relative urls (e.g. `/api/users`) are passed to the service as is.
+    ///
+    /// With the `v8` feature, the service runs on a tokio runtime created for this `ElmRoot`. Bindings called
+    /// from async code block on the surrounding runtime instead, which therefore has to be multi threaded.
+    pub fn http_service<S, B>(self, service: S) -> Self
+    where
+        S: tower_service::Service<
+                ::http::Request<Full<::bytes::Bytes>>,
+                Response = ::http::Response<B>,
+            > + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        B: http_body::Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self {
+            http: Some(HttpService::new(service)),
+            ..self
+        }
+    }
+
+    /// Prepare an Elm function for execution.
+    ///
+    /// The given function name should be in the same form as you would call it in your Elm project when
+    /// not using import aliases or unqualified imports.
+    ///
+    /// E.g. if your function `myFun` is defined in the module `MyModule.Submodule`, you would pass in `MyModule.Submodule.myFun`.
+    ///
+    /// The input and output types intended to be passed in subsequent calls have to be known at this point,
+    /// either by type inference or by explitely specifying them. The reason for this is that we generate a wrapper
+    /// application module for the requested function which needs type annotations (at least the type annotation for the port cannot be inferred).
+    #[cfg(feature = "v8")]
+    pub fn prepare<I, O>(&self, fully_qualified_function: &str) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![elm_type::port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+        )?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare<I, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![elm_type::port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+        )?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Prepare an Elm function taking two arguments for execution.
+    ///
+    /// Works like `prepare`, but the arguments are passed as a tuple to `call`,
+    /// e.g. `handle.call((a, b))` calls `MyModule.myFun a b`.
+    #[cfg(feature = "v8")]
+    pub fn prepare2<A, B, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<(A, B), O>>
+    where
+        A: ElmCompatible,
+        B: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![
+                    elm_type::port_type::<A>(identity, dependencies)?,
+                    elm_type::port_type::<B>(identity, dependencies)?,
+                ])
+            },
+        )?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare2<A, B, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<(A, B), O>>
+    where
+        A: ElmCompatible,
+        B: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![
+                    elm_type::port_type::<A>(identity, dependencies)?,
+                    elm_type::port_type::<B>(identity, dependencies)?,
+                ])
+            },
+        )?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Prepare an Elm function taking three arguments for execution.
+    ///
+    /// Works like `prepare`, but the arguments are passed as a tuple to `call`,
+    /// e.g. `handle.call((lo, hi, x))` calls `MyModule.clamp lo hi x`.
+    ///
+    /// Elm functions with more arguments are not supported, since Elm tuples (which are used to pass
+    /// the arguments into the binding) have at most three elements. Use a record for these instead.
+    #[cfg(feature = "v8")]
+    pub fn prepare3<A, B, C, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<(A, B, C), O>>
+    where
+        A: ElmCompatible,
+        B: ElmCompatible,
+        C: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![
+                    elm_type::port_type::<A>(identity, dependencies)?,
+                    elm_type::port_type::<B>(identity, dependencies)?,
+                    elm_type::port_type::<C>(identity, dependencies)?,
+                ])
+            },
+        )?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare3<A, B, C, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<(A, B, C), O>>
+    where
+        A: ElmCompatible,
+        B: ElmCompatible,
+        C: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let elm_binding = self.prepare_shared::<O>(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![
+                    elm_type::port_type::<A>(identity, dependencies)?,
+                    elm_type::port_type::<B>(identity, dependencies)?,
+                    elm_type::port_type::<C>(identity, dependencies)?,
+                ])
+            },
+        )?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Like `prepare`, but infers the Elm types from the `JsonSchema` implementations of the input and output types
+    /// instead of tracing their `Deserialize` implementations.
+    /// This supports untagged enums, `#[serde(flatten)]` and custom `Deserialize` implementations.
+    #[cfg(all(feature = "v8", feature = "schemars"))]
+    pub fn prepare_with_schema<I, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible + schemars::JsonSchema,
+        O: ElmCompatible + schemars::JsonSchema,
+    {
+        let elm_binding = self.prepare_binding(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![elm_type::schema_port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+            elm_type::schema_port_type::<O>,
+        )?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(all(feature = "quickjs", feature = "schemars"))]
+    pub async fn prepare_with_schema<I, O>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible + schemars::JsonSchema,
+        O: ElmCompatible + schemars::JsonSchema,
+    {
+        let elm_binding = self.prepare_binding(
+            BindingTarget::function(fully_qualified_function)?,
+            |dependencies| {
+                Ok(vec![elm_type::schema_port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+            elm_type::schema_port_type::<O>,
+        )?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Evaluate a top-level Elm value that is not a function, e.g. `Config.defaultConfig`.
+    ///
+    /// The value is compiled into a binding without (meaningful) flags and evaluated once.
+    #[cfg(feature = "v8")]
+    pub fn value<O>(&self, fully_qualified_value: &str) -> Result<O>
+    where
+        O: ElmCompatible,
+    {
+        let elm_binding = self
+            .prepare_shared::<O>(BindingTarget::function(fully_qualified_value)?, |_| {
+                Ok(Vec::new())
+            })?;
+        v8::prepare::<(), O>(self, elm_binding)?.call(())
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn value<O>(&self, fully_qualified_value: &str) -> Result<O>
+    where
+        O: ElmCompatible,
+    {
+        let elm_binding = self
+            .prepare_shared::<O>(BindingTarget::function(fully_qualified_value)?, |_| {
+                Ok(Vec::new())
+            })?;
+        quickjs::prepare::<(), O>(self, elm_binding)
+            .await?
+            .call(())
+            .await
+    }
+
+    /// Prepare an arbitrary Elm expression evaluating to a function for execution,
+    /// e.g. `\xs -> List.sum (List.map Math.square xs)` or `String.repeat 3`.
+    ///
+    /// The given modules are imported (unaliased) into the binding, so the expression can refer to
+    /// their values qualified with the module name. The default imports of Elm (e.g. `List` or `String`) are always available.
+    #[cfg(feature = "v8")]
+    pub fn prepare_expr<I, O>(
+        &self,
+        expression: &str,
+        imports: &[&str],
+    ) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::expression(expression, imports);
+        let elm_binding = self.prepare_shared::<O>(target, |dependencies| {
+            Ok(vec![elm_type::port_type::<I>(
+                elm_type::wrap_in_round_brackets,
+                dependencies,
+            )?])
+        })?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_expr<I, O>(
+        &self,
+        expression: &str,
+        imports: &[&str],
+    ) -> Result<ElmFunctionHandle<I, O>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::expression(expression, imports);
+        let elm_binding = self.prepare_shared::<O>(target, |dependencies| {
+            Ok(vec![elm_type::port_type::<I>(
+                elm_type::wrap_in_round_brackets,
+                dependencies,
+            )?])
+        })?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Evaluate an arbitrary Elm expression only using the default imports of Elm, e.g. `String.reverse "abc"`.
+    #[cfg(feature = "v8")]
+    pub fn eval<O>(&self, expression: &str) -> Result<O>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::expression(expression, &[]);
+        let elm_binding = self.prepare_shared::<O>(target, |_| Ok(Vec::new()))?;
+        v8::prepare::<(), O>(self, elm_binding)?.call(())
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn eval<O>(&self, expression: &str) -> Result<O>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::expression(expression, &[]);
+        let elm_binding = self.prepare_shared::<O>(target, |_| Ok(Vec::new()))?;
+        quickjs::prepare::<(), O>(self, elm_binding)
+            .await?
+            .call(())
+            .await
+    }
+
+    /// Prepare an Elm `Json.Decode.Decoder`, e.g. `Api.User.decoder`, for execution.
+    ///
+    /// The returned handle decodes Json (given as a string or `serde_json::Value`) with the Elm decoder
+    /// and converts the decoded Elm value into `O`.
+    #[cfg(feature = "v8")]
+    pub fn prepare_decoder<O>(&self, fully_qualified_decoder: &str) -> Result<ElmDecoderHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target =
+            BindingTarget::function(fully_qualified_decoder)?.fallible("String", decode_value);
+        let elm_binding = self.prepare_shared::<O>(target, json_value_input)?;
+        Ok(ElmDecoderHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_decoder<O>(
+        &self,
+        fully_qualified_decoder: &str,
+    ) -> Result<ElmDecoderHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target =
+            BindingTarget::function(fully_qualified_decoder)?.fallible("String", decode_value);
+        let elm_binding = self.prepare_shared::<O>(target, json_value_input)?;
+        Ok(ElmDecoderHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare an Elm Json encoder (a function `a -> Json.Encode.Value`), e.g. `Api.User.encode`, for execution.
+    ///
+    /// The returned handle converts the given Rust value into the Elm value, runs the encoder and returns the produced Json,
+    /// either as a `serde_json::Value` or as the string rendered by `Json.Encode.encode`.
+    #[cfg(feature = "v8")]
+    pub fn prepare_encoder<I>(&self, fully_qualified_encoder: &str) -> Result<ElmEncoderHandle<I>>
+    where
+        I: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_encoder)?.map(encode_value);
+        let elm_binding = self.prepare_binding(
+            target,
+            |dependencies| {
+                Ok(vec![elm_type::port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+            json_string_output,
+        )?;
+        Ok(ElmEncoderHandle {
+            handle: v8::prepare(self, elm_binding)?,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_encoder<I>(
+        &self,
+        fully_qualified_encoder: &str,
+    ) -> Result<ElmEncoderHandle<I>>
+    where
+        I: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_encoder)?.map(encode_value);
+        let elm_binding = self.prepare_binding(
+            target,
+            |dependencies| {
+                Ok(vec![elm_type::port_type::<I>(
+                    elm_type::wrap_in_round_brackets,
+                    dependencies,
+                )?])
+            },
+            json_string_output,
+        )?;
+        Ok(ElmEncoderHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+        })
+    }
+
+    /// Check that the given values survive a round trip through serde and the given Elm decoder and encoder.
+    ///
+    /// Each value is serialized with serde, decoded with the Elm decoder, encoded again with the Elm encoder
+    /// and deserialized with serde. The first value which fails at any of these stages or differs
+    /// from the original value afterwards results in an `Error::CodecRoundtripError`.
+    #[cfg(feature = "v8")]
+    pub fn check_codec_roundtrip<T>(
+        &self,
+        fully_qualified_decoder: &str,
+        fully_qualified_encoder: &str,
+        values: &[T],
+    ) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let target = BindingTarget::roundtrip(fully_qualified_decoder, fully_qualified_encoder)?;
+        let elm_binding = self.prepare_shared::<serde_json::Value>(target, json_value_input)?;
+        let handle: ElmFunctionHandle<serde_json::Value, _> = v8::prepare(self, elm_binding)?;
+        for value in values {
+            let serialized = serde_json::to_value(value)?;
+            let output = handle.call(serialized.clone())?;
+            json::check_roundtrip(value, serialized, output)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn check_codec_roundtrip<T>(
+        &self,
+        fully_qualified_decoder: &str,
+        fully_qualified_encoder: &str,
+        values: &[T],
+    ) -> Result<()>
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let target = BindingTarget::roundtrip(fully_qualified_decoder, fully_qualified_encoder)?;
+        let elm_binding = self.prepare_shared::<serde_json::Value>(target, json_value_input)?;
+        let handle: ElmFunctionHandle<serde_json::Value, _> =
+            quickjs::prepare(self, elm_binding).await?;
+        for value in values {
+            let serialized = serde_json::to_value(value)?;
+            let output = handle.call(serialized.clone()).await?;
+            json::check_roundtrip(value, serialized, output)?;
+        }
+        Ok(())
+    }
+
+    /// Prepare an `elm/parser` parser (a `Parser.Parser a`), e.g. `Query.parser`, for execution.
+    ///
+    /// The returned handle runs `Parser.run` on the given input and returns either the parsed value or
+    /// the `DeadEnd`s of the failed parser. Your Elm project needs to depend on `elm/parser` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_parser<O>(&self, fully_qualified_parser: &str) -> Result<ElmParserHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_parser)?
+            .fallible(parser::DEAD_ENDS_TYPE, parser::run_parser);
+        let elm_binding = self.prepare_shared::<O>(target, parser::parser_input)?;
+        Ok(ElmParserHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_parser<O>(
+        &self,
+        fully_qualified_parser: &str,
+    ) -> Result<ElmParserHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_parser)?
+            .fallible(parser::DEAD_ENDS_TYPE, parser::run_parser);
+        let elm_binding = self.prepare_shared::<O>(target, parser::parser_input)?;
+        Ok(ElmParserHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a function returning a `Task x a`, e.g. `Api.fetchUser`, for execution.
+    ///
+    /// The returned handle performs the task for the given input and returns its result or the error it failed with.
+    /// Timers, `Time.now` and `Math.random` run on a virtual clock (see `ElmRoot::start_time`),
+    /// so tasks like `Process.sleep` complete immediately and deterministically.
+    #[cfg(feature = "v8")]
+    pub fn prepare_task<I, O, X>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmTaskHandle<I, O, X>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+        X: ElmCompatible,
+    {
+        let (error_type, map_error) = task::task_error::<X>()?;
+        let target =
+            BindingTarget::function(fully_qualified_function)?.task(&error_type, map_error);
+        let elm_binding = self.prepare_shared::<O>(target, task::task_input::<I, X>)?;
+        Ok(ElmTaskHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_task<I, O, X>(
+        &self,
+        fully_qualified_function: &str,
+    ) -> Result<ElmTaskHandle<I, O, X>>
+    where
+        I: ElmCompatible,
+        O: ElmCompatible,
+        X: ElmCompatible,
+    {
+        let (error_type, map_error) = task::task_error::<X>()?;
+        let target =
+            BindingTarget::function(fully_qualified_function)?.task(&error_type, map_error);
+        let elm_binding = self.prepare_shared::<O>(target, task::task_input::<I, X>)?;
+        Ok(ElmTaskHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a route parser (a function `Url.Url -> Maybe route`), e.g. `Route.fromUrl`, for execution.
+    ///
+    /// The returned handle takes urls as strings (absolute, or path-only like `/users/1` which are resolved against `http://localhost`),
+    /// builds the `Url.Url` via `Url.fromString` and returns the parsed route.
+    /// Urls which `Url.fromString` cannot parse result in an `Error::InvalidUrl` (as opposed to `None` for unknown routes).
+    /// Your Elm project needs to depend on `elm/url` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_url_parser<O>(
+        &self,
+        fully_qualified_route_parser: &str,
+    ) -> Result<ElmUrlParserHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_route_parser)?
+            .fallible(url::URL_ERROR_TYPE, url::parse_url);
+        let elm_binding = self.prepare_shared::<O>(target, url::url_input)?;
+        Ok(ElmUrlParserHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_url_parser<O>(
+        &self,
+        fully_qualified_route_parser: &str,
+    ) -> Result<ElmUrlParserHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_route_parser)?
+            .fallible(url::URL_ERROR_TYPE, url::parse_url);
+        let elm_binding = self.prepare_shared::<O>(target, url::url_input)?;
+        Ok(ElmUrlParserHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare an Elm `Bytes.Decode.Decoder`, e.g. `Wire.decoder`, for execution.
+    ///
+    /// The returned handle builds `Bytes` from the given bytes, runs `Bytes.Decode.decode`
+    /// and returns the decoded value, or `None` if the decoder failed. Your Elm project needs to depend on `elm/bytes` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_bytes_decoder<O>(
+        &self,
+        fully_qualified_decoder: &str,
+    ) -> Result<ElmBytesDecoderHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_decoder)?
+            .fallible("String", bytes::decode_bytes);
+        let elm_binding = self.prepare_shared::<O>(target, bytes::bytes_input)?;
+        Ok(ElmBytesDecoderHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_bytes_decoder<O>(
+        &self,
+        fully_qualified_decoder: &str,
+    ) -> Result<ElmBytesDecoderHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_decoder)?
+            .fallible("String", bytes::decode_bytes);
+        let elm_binding = self.prepare_shared::<O>(target, bytes::bytes_input)?;
+        Ok(ElmBytesDecoderHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a function returning an Elm `Bytes.Encode.Encoder` (`a -> Bytes.Encode.Encoder`), e.g. `Wire.encode`, for execution.
+    ///
+    /// The returned handle converts the given Rust value into the Elm value, runs the encoder and returns the produced bytes.
+    #[cfg(feature = "v8")]
+    pub fn prepare_bytes_encoder<I>(
+        &self,
+        fully_qualified_encoder: &str,
+    ) -> Result<ElmFunctionHandle<I, Vec<u8>>>
+    where
+        I: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_encoder)?.map(bytes::encode_bytes);
+        let elm_binding = self.prepare_shared::<Vec<u8>>(target, |dependencies| {
+            bytes::bytes_dependencies(dependencies);
+            Ok(vec![elm_type::port_type::<I>(
+                elm_type::wrap_in_round_brackets,
+                dependencies,
+            )?])
+        })?;
+        v8::prepare(self, elm_binding)
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_bytes_encoder<I>(
+        &self,
+        fully_qualified_encoder: &str,
+    ) -> Result<ElmFunctionHandle<I, Vec<u8>>>
+    where
+        I: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_encoder)?.map(bytes::encode_bytes);
+        let elm_binding = self.prepare_shared::<Vec<u8>>(target, |dependencies| {
+            bytes::bytes_dependencies(dependencies);
+            Ok(vec![elm_type::port_type::<I>(
+                elm_type::wrap_in_round_brackets,
+                dependencies,
+            )?])
+        })?;
+        quickjs::prepare(self, elm_binding).await
+    }
+
+    /// Prepare an Elm `Random.Generator`, e.g. `Gen.level`, for sampling values.
+    ///
+    /// Your Elm project needs to depend on `elm/random` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_generator<O>(
+        &self,
+        fully_qualified_generator: &str,
+    ) -> Result<ElmGeneratorHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target =
+            BindingTarget::function(fully_qualified_generator)?.map(random::sample_generator);
+        let elm_binding =
+            self.prepare_binding(target, random::sample_input, elm_type::list_port_type::<O>)?;
+        Ok(ElmGeneratorHandle {
+            handle: v8::prepare(self, elm_binding)?,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_generator<O>(
+        &self,
+        fully_qualified_generator: &str,
+    ) -> Result<ElmGeneratorHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target =
+            BindingTarget::function(fully_qualified_generator)?.map(random::sample_generator);
+        let elm_binding =
+            self.prepare_binding(target, random::sample_input, elm_type::list_port_type::<O>)?;
+        Ok(ElmGeneratorHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+        })
+    }
+
+    /// Prepare an `elm-explorations/test` (1.x) `Fuzzer`, e.g. `Fuzzers.order`, for generating values.
+    ///
+    /// Fuzzers usually live in the `tests` directory and `elm-explorations/test` is a test dependency, which both are
+    /// not available to `elm make`. The binding is therefore compiled in a temporary copy of the project
+    /// (see `expose_private`), in which the test dependencies and the `tests` directory are added to the regular ones.
+    #[cfg(feature = "v8")]
+    pub fn prepare_fuzzer<O>(&self, fully_qualified_fuzzer: &str) -> Result<ElmFuzzerHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_fuzzer)?
+            .with_test_dependencies("tests")
+            .fallible("String", fuzz::run_fuzzer);
+        let elm_binding =
+            self.prepare_binding(target, fuzz::fuzzer_input, elm_type::list_port_type::<O>)?;
+        Ok(ElmFuzzerHandle {
+            handle: v8::prepare(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_fuzzer<O>(
+        &self,
+        fully_qualified_fuzzer: &str,
+    ) -> Result<ElmFuzzerHandle<O>>
+    where
+        O: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_fuzzer)?
+            .with_test_dependencies("tests")
+            .fallible("String", fuzz::run_fuzzer);
+        let elm_binding =
+            self.prepare_binding(target, fuzz::fuzzer_input, elm_type::list_port_type::<O>)?;
+        Ok(ElmFuzzerHandle {
+            handle: quickjs::prepare(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Runs all exposed `elm-explorations/test` (1.x) `Test`s of the Elm modules in the given directory
+    /// (relative to the directory containing the elm.json, e.g. `"tests/"`) and reports the result of every test.
+    ///
+    /// Fuzz tests are run 100 times with a fixed seed. Like `prepare_fuzzer`, the tests are compiled in a
+    /// temporary copy of the project, so `elm-explorations/test` only needs to be a test dependency.
+    #[cfg(feature = "v8")]
+    pub fn run_elm_tests(&self, directory: &str) -> Result<ElmTestReport> {
+        let elm_binding = self.prepare_elm_tests(directory)?;
+        let handle: ElmFunctionHandle<(i32, i32), Vec<ElmTestResult>> =
+            v8::prepare(self, elm_binding)?;
+        Ok(ElmTestReport {
+            results: handle.call((elm_test::SEED, elm_test::RUNS))?,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn run_elm_tests(&self, directory: &str) -> Result<ElmTestReport> {
+        let elm_binding = self.prepare_elm_tests(directory)?;
+        let handle: ElmFunctionHandle<(i32, i32), Vec<ElmTestResult>> =
+            quickjs::prepare(self, elm_binding).await?;
+        Ok(ElmTestReport {
+            results: handle.call((elm_test::SEED, elm_test::RUNS)).await?,
+        })
+    }
+
+    fn prepare_elm_tests(&self, directory: &str) -> Result<ElmBinding> {
+        let tests =
+            elm_test::find_tests(&isolation::project_dir(&self.root_path)?.join(directory))?;
+        let mut modules = tests
+            .iter()
+            .filter_map(|test| test.rsplit_once('.').map(|(module, _)| module))
+            .collect::<Vec<_>>();
+        modules.dedup();
+        let target = BindingTarget::expression(&elm_test::run_tests(&tests), &modules)
+            .with_name("ElmTests")
+            .with_test_dependencies(directory);
+        self.prepare_binding(target, elm_test::tests_input, elm_test::results_output)
+    }
 
-impl ElmRoot {
-    /// Create an `ElmRoot` for a given directory.
-    /// The directory should NOT be the one with the elm.json file in it,
-    /// but the directory with the .elm files in it (with the default elm.json file, this is usually ./src).
+    /// Prepare an Elm program (The Elm Architecture) for execution, given the module defining it.
     ///
-    /// The reason for this is: You may choose any source-directories in your elm.json you like,
-    /// so we would need to parse elm.json and check all directories for the specified module.
-    pub fn new<P>(path: P) -> Result<Self>
+    /// The module has to expose `init : Flags -> ( Model, Cmd Msg )`, `update : Msg -> Model -> ( Model, Cmd Msg )`
+    /// and `subscriptions : Model -> Sub Msg`, which are wrapped in a `Platform.worker` (so the `view` is ignored, see `prepare_element` to interact with it).
+    /// Messages are passed from Rust to the program, the model and the messages sent through the ports of the program
+    /// are passed back. Custom types such as the `Msg` type usually need an explicit mapping via `#[derive(ElmType)]`.
+    #[cfg(feature = "v8")]
+    pub fn prepare_program<Flags, Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<Flags, Msg, Model>>
     where
-        PathBuf: From<P>,
+        Flags: ElmCompatible,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
     {
-        Ok(Self {
-            root_path: PathBuf::from(path),
-            debug: false,
+        let elm_binding =
+            self.prepare_program_binding::<Flags, Msg, Model>(program_module, ProgramKind::Worker)?;
+        Ok(ElmProgramHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
         })
     }
 
-    /// Set the `ElmRoot` to debug mode.
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_program<Flags, Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<Flags, Msg, Model>>
+    where
+        Flags: ElmCompatible,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let elm_binding =
+            self.prepare_program_binding::<Flags, Msg, Model>(program_module, ProgramKind::Worker)?;
+        Ok(ElmProgramHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a `Browser.element` program for headless interaction tests, given the module defining it.
     ///
-    /// This has two effects:
-    /// - Some println! logs to describe what the crate is doing
-    /// - The temporarily created files are not deleted
-    pub fn debug(self) -> Self {
-        Self {
-            debug: true,
-            ..self
-        }
+    /// Like `prepare_program`, but the module also has to expose `view : Model -> Html Msg`, which is rendered into
+    /// a minimal DOM. Events such as clicks are dispatched to the elements of the rendered view (see `ElmProgram::click`),
+    /// so the messages are produced by the event decoders of the view. Your Elm project needs to depend on
+    /// `elm/browser` and `elm/html` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_element<Flags, Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<Flags, Msg, Model>>
+    where
+        Flags: ElmCompatible,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let elm_binding = self
+            .prepare_program_binding::<Flags, Msg, Model>(program_module, ProgramKind::Element)?;
+        Ok(ElmProgramHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
+        })
     }
 
-    /// Prepare an Elm function for execution.
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_element<Flags, Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<Flags, Msg, Model>>
+    where
+        Flags: ElmCompatible,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let elm_binding = self
+            .prepare_program_binding::<Flags, Msg, Model>(program_module, ProgramKind::Element)?;
+        Ok(ElmProgramHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a `Browser.sandbox` program for headless interaction tests, given the module defining it.
     ///
-    /// The given function name should be in the same form as you would call it in your Elm project when
-    /// not using import aliases or unqualified imports.
+    /// The module has to expose `init : Model`, `update : Msg -> Model -> Model` and `view : Model -> Html Msg`.
+    /// Otherwise it behaves like `prepare_element`, with `()` as the flags passed to `init`.
+    #[cfg(feature = "v8")]
+    pub fn prepare_sandbox<Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<(), Msg, Model>>
+    where
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let elm_binding =
+            self.prepare_program_binding::<(), Msg, Model>(program_module, ProgramKind::Sandbox)?;
+        Ok(ElmProgramHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_sandbox<Msg, Model>(
+        &self,
+        program_module: &str,
+    ) -> Result<ElmProgramHandle<(), Msg, Model>>
+    where
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let elm_binding =
+            self.prepare_program_binding::<(), Msg, Model>(program_module, ProgramKind::Sandbox)?;
+        Ok(ElmProgramHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare a `Browser.application` for headless routing tests, given the module defining it.
     ///
-    /// E.g. if your function `myFun` is defined in the module `MyModule.Submodule`, you would pass in `MyModule.Submodule.myFun`.
+    /// The module has to expose the functions passed to `Browser.application`: `init`, `update`, `subscriptions`,
+    /// `view`, `onUrlRequest` and `onUrlChange`. Each instance starts at a url given to `init`. Navigations requested
+    /// via `Browser.Navigation` are recorded instead of performed (see `ElmProgram::take_navigations`), url changes can be
+    /// triggered from Rust (see `ElmProgram::change_url`) and clicked links are passed to `onUrlRequest`.
+    #[cfg(feature = "v8")]
+    pub fn prepare_application<Flags>(
+        &self,
+        application_module: &str,
+    ) -> Result<ElmApplicationHandle<Flags>>
+    where
+        Flags: ElmCompatible,
+    {
+        let elm_binding = self.prepare_program_binding::<Flags, (), ()>(
+            application_module,
+            ProgramKind::Application,
+        )?;
+        Ok(ElmApplicationHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_application<Flags>(
+        &self,
+        application_module: &str,
+    ) -> Result<ElmApplicationHandle<Flags>>
+    where
+        Flags: ElmCompatible,
+    {
+        let elm_binding = self.prepare_program_binding::<Flags, (), ()>(
+            application_module,
+            ProgramKind::Application,
+        )?;
+        Ok(ElmApplicationHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    /// Prepare an existing `Platform.worker`, given the module exposing it as `main`, e.g. `Worker`.
     ///
-    /// The input and output types intended to be passed in subsequent calls have to be known at this point,
-    /// either by type inference or by explitely specifying them. The reason for this is that we generate a wrapper
-    /// application module for the requested function which needs type annotations (at least the type annotation for the port cannot be inferred).
+    /// Unlike `prepare_program`, the worker is used as is and can only be driven through its own ports:
+    /// values are sent to its incoming ports and the values of its outgoing ports are received via channels.
     #[cfg(feature = "v8")]
-    pub fn prepare<I, O>(&self, fully_qualified_function: &str) -> Result<ElmFunctionHandle<I, O>>
+    pub fn prepare_worker<Flags>(&self, worker_module: &str) -> Result<ElmWorkerHandle<Flags>>
+    where
+        Flags: Serialize,
+    {
+        let elm_binding = self.prepare_worker_binding(worker_module)?;
+        Ok(ElmWorkerHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_worker<Flags>(&self, worker_module: &str) -> Result<ElmWorkerHandle<Flags>>
+    where
+        Flags: Serialize,
+    {
+        let elm_binding = self.prepare_worker_binding(worker_module)?;
+        Ok(ElmWorkerHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    fn prepare_worker_binding(&self, worker_module: &str) -> Result<ElmBinding> {
+        let seed = Uuid::now_v7().as_u128();
+        log!(self, "Running with seed: {seed}");
+        let binding_module_name = format!("{}_Worker{seed}", worker_module.replace('.', "_"));
+        log!(self, "Inferred binding module name: {binding_module_name}");
+        let binding_elm = worker::worker_elm(&binding_module_name, worker_module);
+        self.build_binding(
+            seed,
+            binding_module_name,
+            binding_elm,
+            &[worker_module.to_owned()],
+            None,
+        )
+    }
+
+    /// Prepare an Elm `update` function (`Msg -> Model -> ( Model, Cmd Msg )`), e.g. `Page.update`, for folding messages.
+    ///
+    /// Calling the returned handle with an initial model and a list of messages applies the messages one after another
+    /// in a single call and returns the model after each message. The commands are discarded.
+    #[cfg(feature = "v8")]
+    pub fn prepare_update<Msg, Model>(
+        &self,
+        fully_qualified_update: &str,
+    ) -> Result<ElmFunctionHandle<(Model, Vec<Msg>), Vec<Model>>>
     where
-        I: DeserializeOwned,
-        O: DeserializeOwned,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
     {
-        let elm_binding = self.prepare_shared::<I, O>(fully_qualified_function)?;
+        let elm_binding = self.prepare_update_binding::<Msg, Model>(fully_qualified_update)?;
         v8::prepare(self, elm_binding)
     }
 
     #[cfg(feature = "quickjs")]
-    pub async fn prepare<I, O>(
+    pub async fn prepare_update<Msg, Model>(
         &self,
-        fully_qualified_function: &str,
-    ) -> Result<ElmFunctionHandle<I, O>>
+        fully_qualified_update: &str,
+    ) -> Result<ElmFunctionHandle<(Model, Vec<Msg>), Vec<Model>>>
     where
-        I: DeserializeOwned,
-        O: DeserializeOwned,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
     {
-        let elm_binding = self.prepare_shared::<I, O>(fully_qualified_function)?;
+        let elm_binding = self.prepare_update_binding::<Msg, Model>(fully_qualified_update)?;
         quickjs::prepare(self, elm_binding).await
     }
 
-    fn prepare_shared<I, O>(&self, fully_qualified_function: &str) -> Result<ElmBinding>
+    fn prepare_update_binding<Msg, Model>(&self, fully_qualified_update: &str) -> Result<ElmBinding>
+    where
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let target = BindingTarget::function(fully_qualified_update)?.map(program::fold_update);
+        self.prepare_binding(
+            target,
+            |dependencies| {
+                Ok(vec![
+                    elm_type::port_type::<Model>(identity, dependencies)?,
+                    elm_type::list_port_type::<Msg>(identity, dependencies)?,
+                ])
+            },
+            elm_type::list_port_type::<Model>,
+        )
+    }
+
+    fn prepare_program_binding<Flags, Msg, Model>(
+        &self,
+        program_module: &str,
+        kind: ProgramKind,
+    ) -> Result<ElmBinding>
+    where
+        Flags: ElmCompatible,
+        Msg: ElmCompatible,
+        Model: ElmCompatible,
+    {
+        let seed = Uuid::now_v7().as_u128();
+        log!(self, "Running with seed: {seed}");
+        let mut dependencies = ElmDependencies::default();
+        let flags = elm_type::port_type::<Flags>(identity, &mut dependencies)?;
+        let msg = elm_type::port_type::<Msg>(identity, &mut dependencies)?;
+        let model = elm_type::port_type::<Model>(identity, &mut dependencies)?;
+        log!(
+            self,
+            "Inferred flags, msg and model types: {}, {}, {}",
+            flags.input_annotation(),
+            msg.input_annotation(),
+            model.output_annotation()
+        );
+        dependencies.import(program_module);
+        if kind != ProgramKind::Worker {
+            dependencies.import("Browser");
+            dependencies.import("Html");
+        }
+
+        let binding_module_name = format!("{}_Program{seed}", program_module.replace('.', "_"));
+        log!(self, "Inferred binding module name: {binding_module_name}");
+        let imports = dependencies
+            .imports()
+            .map(|module| format!("import {module}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let binding_elm = program::program_elm(
+            &binding_module_name,
+            program_module,
+            kind,
+            &imports,
+            &dependencies.declarations(),
+            &flags,
+            &msg,
+            &model,
+        );
+        self.build_binding(
+            seed,
+            binding_module_name,
+            binding_elm,
+            &[program_module.to_owned()],
+            None,
+        )
+    }
+
+    /// Prepare an Elm view function (`input -> Html msg`), e.g. `Page.view`, for rendering.
+    ///
+    /// The view is rendered by a `Browser.element` into a minimal DOM, which is returned as a tree of `HtmlNode`s.
+    /// Its `Display` implementation produces normalized Html, e.g. for snapshot tests or comparisons with
+    /// server-rendered templates. Your Elm project needs to depend on `elm/browser` and `elm/html` for this.
+    #[cfg(feature = "v8")]
+    pub fn prepare_view<I>(&self, fully_qualified_view: &str) -> Result<ElmViewHandle<I>>
+    where
+        I: ElmCompatible,
+    {
+        let elm_binding = self.prepare_view_binding::<I>(fully_qualified_view)?;
+        Ok(ElmViewHandle {
+            module: v8::prepare_program(self, elm_binding)?,
+            _type: PhantomData,
+        })
+    }
+
+    #[cfg(feature = "quickjs")]
+    pub async fn prepare_view<I>(&self, fully_qualified_view: &str) -> Result<ElmViewHandle<I>>
+    where
+        I: ElmCompatible,
+    {
+        let elm_binding = self.prepare_view_binding::<I>(fully_qualified_view)?;
+        Ok(ElmViewHandle {
+            module: quickjs::prepare_program(self, elm_binding).await?,
+            _type: PhantomData,
+        })
+    }
+
+    fn prepare_view_binding<I>(&self, fully_qualified_view: &str) -> Result<ElmBinding>
+    where
+        I: ElmCompatible,
+    {
+        let BindingTarget {
+            name,
+            expression,
+            imports: imports_to_expose,
+            ..
+        } = BindingTarget::function(fully_qualified_view)?;
+        let seed = Uuid::now_v7().as_u128();
+        log!(self, "Running with seed: {seed}");
+        let mut dependencies = ElmDependencies::default();
+        let input = elm_type::port_type::<I>(identity, &mut dependencies)?;
+        log!(self, "Inferred input type: {}", input.input_annotation());
+        dependencies.import("Browser");
+        dependencies.import("Html");
+        for module in &imports_to_expose {
+            dependencies.import(module);
+        }
+
+        let binding_module_name = format!("{name}_View{seed}");
+        log!(self, "Inferred binding module name: {binding_module_name}");
+        let imports = dependencies
+            .imports()
+            .map(|module| format!("import {module}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let binding_elm = view::view_elm(
+            &binding_module_name,
+            &expression,
+            &imports,
+            &dependencies.declarations(),
+            &input,
+        );
+        self.build_binding(
+            seed,
+            binding_module_name,
+            binding_elm,
+            &imports_to_expose,
+            None,
+        )
+    }
+
+    /// Generates and compiles a binding for the given target returning values of type `O`.
+    /// The arguments of the target are described by the port types returned by `input_port_types`.
+    fn prepare_shared<O>(
+        &self,
+        target: BindingTarget,
+        input_port_types: impl FnOnce(&mut ElmDependencies) -> Result<Vec<PortType>>,
+    ) -> Result<ElmBinding>
     where
-        I: DeserializeOwned,
-        O: DeserializeOwned,
+        O: ElmCompatible,
     {
+        self.prepare_binding(target, input_port_types, elm_type::port_type::<O>)
+    }
+
+    /// Generates and compiles a binding for the given target.
+    /// The arguments and the result of the target are described by the given port types.
+    fn prepare_binding(
+        &self,
+        target: BindingTarget,
+        input_port_types: impl FnOnce(&mut ElmDependencies) -> Result<Vec<PortType>>,
+        output_port_type: impl FnOnce(fn(String) -> String, &mut ElmDependencies) -> Result<PortType>,
+    ) -> Result<ElmBinding> {
         // 0. Extract timestamp because of potential file creation/deletion conflicts
         let seed = Uuid::now_v7().as_u128();
         log!(self, "Running with seed: {seed}");
         // 1. Generate a binding file via the template
-        let input_type = elm_type::convert::<I>(elm_type::wrap_in_round_brackets)?;
+        let mut dependencies = ElmDependencies::default();
+        let input_port_types = input_port_types(&mut dependencies)?;
+        let (input_type, parameters) = binding_flags(&input_port_types);
         log!(self, "Inferred input type: {input_type}");
 
-        let output_type = elm_type::convert::<O>(identity)?;
-        log!(self, "Inferred output type: {output_type}");
+        let BindingTarget {
+            name,
+            expression,
+            imports: imports_to_expose,
+            error_type,
+            task,
+            test_directory,
+        } = target;
 
-        let qualified_segments = fully_qualified_function.split('.').collect::<Vec<_>>();
-        let Some((function_name, module_path_segments)) = qualified_segments.split_last() else {
-            return Err(Box::new(Error::InvalidElmCall(
-                fully_qualified_function.to_owned(),
-            )));
+        let output_port_type = if error_type.is_some() {
+            output_port_type(elm_type::wrap_in_round_brackets, &mut dependencies)?
+        } else {
+            output_port_type(identity, &mut dependencies)?
         };
-        log!(self, "Inferred function name: {function_name}");
-
-        let module_name = module_path_segments.join(".");
-        log!(self, "Inferred module name: {module_name}");
+        let output_type = if let Some(error_type) = &error_type {
+            format!(
+                "{{ ok : Bool, error : Maybe ({error_type}), value : Maybe {} }}",
+                output_port_type.output_annotation()
+            )
+        } else {
+            output_port_type.output_annotation().to_owned()
+        };
+        log!(self, "Inferred output type: {output_type}");
+        log!(self, "Called expression: {expression}");
+        for module in &imports_to_expose {
+            log!(self, "Imported module: {module}");
+            dependencies.import(module);
+        }
 
-        let mut binding_module_name = qualified_segments.join("_");
+        let mut binding_module_name = name;
         binding_module_name.push_str("_Binding");
         binding_module_name.push_str(&seed.to_string());
         log!(self, "Inferred binding module name: {binding_module_name}");
 
+        // Expressions might span multiple lines, so they are placed in their own declaration
+        let function = if expression.contains('\n') {
+            let indented = expression
+                .lines()
+                .map(|line| format!("    {line}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            dependencies.define("expression", format!("expression =\n{indented}"));
+            "expression".to_owned()
+        } else {
+            format!("({expression})")
+        };
+
+        let imports = dependencies
+            .imports()
+            .map(|module| format!("import {module}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let body = binding_body(
+            &function,
+            &input_port_types,
+            &output_port_type,
+            error_type.is_some(),
+            task,
+        );
+
         let binding_elm = BINDING_TEMPLATE
-            .replace("{{ module_path }}", &module_name)
-            .replace("{{ function_name }}", function_name)
+            .replace("{{ imports }}", &imports)
+            .replace("{{ body }}", &body)
+            .replace("{{ declarations }}", &dependencies.declarations())
             .replace("{{ file_name }}", &binding_module_name)
+            .replace("{{ parameters }}", &parameters)
             .replace("{{ input_type }}", &input_type)
             .replace("{{ output_type }}", &output_type);
 
-        let file_name = binding_module_name.clone() + ".elm";
-        let file_path = self.root_path.join(&file_name);
+        self.build_binding(
+            seed,
+            binding_module_name,
+            binding_elm,
+            &imports_to_expose,
+            test_directory.as_deref(),
+        )
+    }
+
+    /// Compiles the generated binding module, in an isolated copy of the project if necessary.
+    fn build_binding(
+        &self,
+        seed: u128,
+        binding_module_name: String,
+        binding_elm: String,
+        imports_to_expose: &[String],
+        test_directory: Option<&str>,
+    ) -> Result<ElmBinding> {
+        // Functions which are not exposed (or need test dependencies) are called via an isolated copy of the project
+        let compiled_binding = if self.expose_private || test_directory.is_some() {
+            let exposed_modules = if self.expose_private {
+                imports_to_expose
+            } else {
+                &[]
+            };
+            let project =
+                IsolatedProject::create(&self.root_path, seed, exposed_modules, test_directory)?;
+            log!(
+                self,
+                "Isolated build directory: {}",
+                project.root_path().display()
+            );
+            let compiled_binding =
+                self.compile_binding(project.root_path(), &binding_module_name, binding_elm);
+            if !self.debug {
+                project.remove()?;
+            }
+            compiled_binding?
+        } else {
+            self.compile_binding(&self.root_path, &binding_module_name, binding_elm)?
+        };
+        Ok(ElmBinding {
+            compiled_binding,
+            binding_module_name,
+        })
+    }
+
+    /// Writes the binding into the given directory and compiles it with the elm compiler.
+    fn compile_binding(
+        &self,
+        build_root: &Path,
+        binding_module_name: &str,
+        binding_elm: String,
+    ) -> Result<String> {
+        let file_name = binding_module_name.to_owned() + ".elm";
+        let file_path = build_root.join(&file_name);
 
         fs::write(&file_path, binding_elm).map_err(Error::map_disk_error(file_path.clone()))?;
 
         // 2. Call the elm-compiler via the CLI to compile the binding file
-        let binding_js_file_name = binding_module_name.clone() + ".js";
+        let binding_js_file_name = binding_module_name.to_owned() + ".js";
         let elm_compile_result = Command::new("elm")
-            .current_dir(&self.root_path)
+            .current_dir(build_root)
             .arg("make")
             .arg(&file_name)
             .arg(format!("--output={binding_js_file_name}"))
@@ -170,18 +1512,13 @@ impl ElmRoot {
             }
         }
 
-        let compiled_binding_file_path = self.root_path.join(binding_js_file_name);
+        let compiled_binding_file_path = build_root.join(binding_js_file_name);
         let compiled_binding_result = fs::read_to_string(&compiled_binding_file_path);
         if !self.debug {
             fs::remove_file(&compiled_binding_file_path)
                 .map_err(Error::map_disk_error(compiled_binding_file_path.clone()))?;
         }
-        let compiled_binding = compiled_binding_result
-            .map_err(Error::map_disk_error(compiled_binding_file_path.clone()))?;
-        Ok(ElmBinding {
-            compiled_binding,
-            binding_module_name,
-        })
+        Ok(compiled_binding_result.map_err(Error::map_disk_error(compiled_binding_file_path))?)
     }
 
     fn write_esm_binding(
@@ -200,11 +1537,269 @@ impl ElmRoot {
     }
 }
 
+/// The flags type of the binding and the pattern of the `run` function destructuring it into the arguments `a0`, `a1`, ...
+/// Multiple arguments are passed as a tuple, values without arguments receive unit flags.
+fn binding_flags(inputs: &[PortType]) -> (String, String) {
+    let arguments = (0..inputs.len())
+        .map(|index| format!("a{index}"))
+        .collect::<Vec<_>>();
+    match inputs {
+        [] => ("()".to_owned(), "_".to_owned()),
+        [input] => (input.input_annotation().to_owned(), arguments.join("")),
+        _ => (
+            format!(
+                "( {} )",
+                inputs
+                    .iter()
+                    .map(PortType::input_annotation)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            format!("( {} )", arguments.join(", ")),
+        ),
+    }
+}
+
+/// The body of the `run` function in the binding template, which calls the Elm function
+/// with the arguments `a0`, `a1`, ... and sends the result to the `out` port.
+///
+/// Fallible functions return a `Result error a`, which is sent as a record `{ ok, error, value }`
+/// (see `json::FallibleOutput`).
+/// Tasks (`Task error a`) are performed and their result is sent the same way.
+fn binding_body(
+    function: &str,
+    inputs: &[PortType],
+    output: &PortType,
+    fallible: bool,
+    task: bool,
+) -> String {
+    let encoded_output = match output {
+        PortType::Native(_) => "output".to_owned(),
+        PortType::Codec { encoder, .. } => format!("({encoder} output)"),
+    };
+    let perform = |task: &str| {
+        format!(
+            "Task.perform out (Task.onError (\\error -> Task.succeed {{ ok = False, error = Just error, value = Nothing }}) (Task.map (\\output -> {{ ok = True, error = Nothing, value = Just {encoded_output} }}) {task}))"
+        )
+    };
+    let all_native = inputs
+        .iter()
+        .all(|input| matches!(input, PortType::Native(_)));
+    let result = if all_native {
+        let arguments = (0..inputs.len())
+            .map(|index| format!(" a{index}"))
+            .collect::<String>();
+        if task {
+            return perform(&format!("({function}{arguments})"));
+        }
+        if !fallible {
+            return match output {
+                PortType::Native(_) => format!("{function}{arguments} |> out"),
+                PortType::Codec { encoder, .. } => {
+                    format!("{function}{arguments} |> {encoder} |> out")
+                }
+            };
+        }
+        format!("{function}{arguments}")
+    } else {
+        let map = match inputs.len() {
+            1 => "Result.map".to_owned(),
+            len => format!("Result.map{len}"),
+        };
+        let arguments = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| match input {
+                PortType::Native(_) => format!(" (Ok a{index})"),
+                PortType::Codec { decoder, .. } => {
+                    format!(" (Json.Decode.decodeValue {decoder} a{index})")
+                }
+            })
+            .collect::<String>();
+        let flatten = if fallible && !task {
+            " |> Result.andThen identity"
+        } else {
+            ""
+        };
+        format!(
+            "({map} {function}{arguments} |> Result.mapError Json.Decode.errorToString{flatten})"
+        )
+    };
+    if task {
+        format!(
+            "case {result} of
+        Ok task ->
+            {}
+
+        Err error ->
+            err error",
+            perform("task")
+        )
+    } else if fallible {
+        format!(
+            "case {result} of
+        Ok output ->
+            out {{ ok = True, error = Nothing, value = Just {encoded_output} }}
+
+        Err error ->
+            out {{ ok = False, error = Just error, value = Nothing }}"
+        )
+    } else {
+        format!(
+            "case {result} of
+        Ok output ->
+            out {encoded_output}
+
+        Err error ->
+            err error"
+        )
+    }
+}
+
+/// Wraps an Elm decoder into a function decoding a Json value into a `Result String a`.
+fn decode_value(decoder: &str) -> String {
+    format!("Json.Decode.decodeValue {decoder} >> Result.mapError Json.Decode.errorToString")
+}
+
+/// Renders the Json produced by the encoder, which is passed to Rust as a string.
+fn encode_value(encoder: &str) -> String {
+    format!("{encoder} >> Json.Encode.encode 0")
+}
+
+/// The Json rendered by `encode_value`
+fn json_string_output(
+    _format_adjustment: fn(String) -> String,
+    dependencies: &mut ElmDependencies,
+) -> Result<PortType> {
+    dependencies.import("Json.Encode");
+    Ok(PortType::Native("String".to_owned()))
+}
+
+/// A single argument passed as Json
+fn json_value_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Json.Decode");
+    Ok(vec![PortType::Native("Json.Decode.Value".to_owned())])
+}
+
+/// The Elm expression a binding calls, e.g. a fully qualified function.
+struct BindingTarget {
+    /// Prefix of the generated binding module name
+    name: String,
+    expression: String,
+    imports: Vec<String>,
+    /// The error type, if the expression returns a `Result error a` instead of the output directly
+    error_type: Option<String>,
+    /// Whether the expression returns a `Task error a`, which is performed to get its result
+    task: bool,
+    /// The test directory (relative to the elm.json), if the expression needs it and the test dependencies of the project
+    test_directory: Option<String>,
+}
+
+impl BindingTarget {
+    fn function(fully_qualified_function: &str) -> Result<Self> {
+        let qualified_segments = fully_qualified_function.split('.').collect::<Vec<_>>();
+        let Some((_, module_path_segments)) = qualified_segments.split_last() else {
+            return Err(Box::new(Error::InvalidElmCall(
+                fully_qualified_function.to_owned(),
+            )));
+        };
+        if module_path_segments.is_empty() {
+            return Err(Box::new(Error::InvalidElmCall(format!(
+                "{fully_qualified_function} is not qualified with its module"
+            ))));
+        }
+        Ok(Self {
+            name: qualified_segments.join("_"),
+            expression: fully_qualified_function.to_owned(),
+            imports: vec![module_path_segments.join(".")],
+            error_type: None,
+            task: false,
+            test_directory: None,
+        })
+    }
+
+    fn expression(expression: &str, imports: &[&str]) -> Self {
+        Self {
+            name: "Expression".to_owned(),
+            expression: expression.trim().to_owned(),
+            imports: imports.iter().map(|module| (*module).to_owned()).collect(),
+            error_type: None,
+            task: false,
+            test_directory: None,
+        }
+    }
+
+    /// Decodes a Json value with the given decoder and encodes the result again with the given encoder.
+    fn roundtrip(fully_qualified_decoder: &str, fully_qualified_encoder: &str) -> Result<Self> {
+        let encoder = Self::function(fully_qualified_encoder)?;
+        let decoder = Self::function(fully_qualified_decoder)?.fallible("String", |decoder| {
+            format!(
+                "{} >> Result.map {fully_qualified_encoder}",
+                decode_value(decoder)
+            )
+        });
+        Ok(Self {
+            imports: [decoder.imports, encoder.imports].concat(),
+            ..decoder
+        })
+    }
+
+    /// Overrides the prefix of the binding module name.
+    fn with_name(self, name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..self
+        }
+    }
+
+    /// Compile the binding with the test dependencies and the given test directory of the project.
+    fn with_test_dependencies(self, test_directory: &str) -> Self {
+        Self {
+            test_directory: Some(test_directory.to_owned()),
+            ..self
+        }
+    }
+
+    /// Wraps the expression, e.g. to convert its result into a port compatible type.
+    fn map(self, wrap: impl FnOnce(&str) -> String) -> Self {
+        Self {
+            expression: wrap(&self.expression),
+            ..self
+        }
+    }
+
+    /// Turns the target into a fallible one by wrapping the expression, e.g. in a call to `Json.Decode.decodeValue`.
+    /// Only targets without codec inputs support error types other than `String`.
+    fn fallible(self, error_type: &str, wrap: impl FnOnce(&str) -> String) -> Self {
+        Self {
+            expression: wrap(&self.expression),
+            error_type: Some(error_type.to_owned()),
+            ..self
+        }
+    }
+
+    /// Turns the target into a task, which is performed and whose result is sent like the result of a fallible target.
+    fn task(self, error_type: &str, wrap: impl FnOnce(&str) -> String) -> Self {
+        Self {
+            task: true,
+            ..self.fallible(error_type, wrap)
+        }
+    }
+}
+
 struct ElmBinding {
     compiled_binding: String,
     binding_module_name: String,
 }
 
+/// Prepends the shims of the browser APIs (timers, `XMLHttpRequest`, the DOM and the navigation)
+/// to the ESM compatible binding.
+fn with_browser_shims(esm_compiled_binding: &str) -> String {
+    view::with_dom_shim(&http::with_http_shim(&clock::with_virtual_clock(
+        &application::with_navigation_shim(esm_compiled_binding),
+    )))
+}
+
 const BINDING_TEMPLATE: &str = include_str!("./templates/Binding.elm.template");
 const TO_ESM_JS: &str = include_str!("./templates/to-esm.mjs");
 
@@ -0,0 +1,155 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{error::Result, json::FallibleOutput, ElmDependencies, ElmFunctionHandle, PortType};
+
+/// A position at which an `elm/parser` parser got stuck, see `Parser.DeadEnd`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeadEnd {
+    pub row: u32,
+    pub col: u32,
+    /// The `Parser.Problem`, rendered similar to the Elm constructor, e.g. `ExpectingSymbol "("`.
+    /// Quotes and backslashes in the expected text are escaped like in an Elm string literal.
+    pub problem: String,
+}
+
+/// The Elm type `DeadEnd`s are sent as.
+pub(crate) const DEAD_ENDS_TYPE: &str = "List { row : Int, col : Int, problem : String }";
+
+/// A handle to an `elm/parser` parser, created via `ElmRoot::prepare_parser`.
+/// Calling it runs `Parser.run` in Elm.
+pub struct ElmParserHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<String, FallibleOutput>,
+    pub(crate) _type: PhantomData<O>,
+}
+
+impl<O> ElmParserHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Parses the given input with the Elm parser.
+    /// Returns the parsed value or the dead ends of the failed parser.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, input: &str) -> Result<std::result::Result<O, Vec<DeadEnd>>> {
+        let result = self.handle.call(input.to_owned())?;
+        parser_output(result)
+    }
+
+    /// Parses the given input with the Elm parser.
+    /// Returns the parsed value or the dead ends of the failed parser.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, input: &str) -> Result<std::result::Result<O, Vec<DeadEnd>>> {
+        let result = self.handle.call(input.to_owned()).await?;
+        parser_output(result)
+    }
+}
+
+fn parser_output<O: DeserializeOwned>(
+    output: FallibleOutput,
+) -> Result<std::result::Result<O, Vec<DeadEnd>>> {
+    match output.into_result()? {
+        Ok(value) => Ok(Ok(serde_json::from_value(value)?)),
+        Err(dead_ends) => Ok(Err(dead_ends)),
+    }
+}
+
+/// Wraps a parser into a function running it and converting the dead ends into port compatible records.
+pub(crate) fn run_parser(parser: &str) -> String {
+    format!("Parser.run {parser} >> Result.mapError (List.map deadEndToRecord)")
+}
+
+/// A single `String` argument, along with the declarations needed by `run_parser`.
+pub(crate) fn parser_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Parser");
+    if dependencies.declare("deadEndToRecord") {
+        dependencies.define("deadEndToRecord", DEAD_END_TO_RECORD.to_owned());
+        dependencies.define("problemToString", PROBLEM_TO_STRING.to_owned());
+    }
+    Ok(vec![PortType::Native("String".to_owned())])
+}
+
+const DEAD_END_TO_RECORD: &str =
+    "deadEndToRecord : Parser.DeadEnd -> { row : Int, col : Int, problem : String }
+deadEndToRecord { row, col, problem } =
+    { row = row, col = col, problem = problemToString problem }";
+
+const PROBLEM_TO_STRING: &str = r#"problemToString : Parser.Problem -> String
+problemToString problem =
+    let
+        quoted text =
+            "\"" ++ (text |> String.replace "\\" "\\\\" |> String.replace "\"" "\\\"") ++ "\""
+    in
+    case problem of
+        Parser.Expecting expected ->
+            "Expecting " ++ quoted expected
+
+        Parser.ExpectingInt ->
+            "ExpectingInt"
+
+        Parser.ExpectingHex ->
+            "ExpectingHex"
+
+        Parser.ExpectingOctal ->
+            "ExpectingOctal"
+
+        Parser.ExpectingBinary ->
+            "ExpectingBinary"
+
+        Parser.ExpectingFloat ->
+            "ExpectingFloat"
+
+        Parser.ExpectingNumber ->
+            "ExpectingNumber"
+
+        Parser.ExpectingVariable ->
+            "ExpectingVariable"
+
+        Parser.ExpectingSymbol symbol ->
+            "ExpectingSymbol " ++ quoted symbol
+
+        Parser.ExpectingKeyword keyword ->
+            "ExpectingKeyword " ++ quoted keyword
+
+        Parser.ExpectingEnd ->
+            "ExpectingEnd"
+
+        Parser.UnexpectedChar ->
+            "UnexpectedChar"
+
+        Parser.Problem message ->
+            "Problem " ++ quoted message
+
+        Parser.BadRepeat ->
+            "BadRepeat""#;
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{parser_input, parser_output};
+    use crate::{json::FallibleOutput, ElmDependencies};
+
+    #[test]
+    fn dead_ends() {
+        let output = parser_output::<u32>(FallibleOutput::failed(json!([
+            { "row": 1, "col": 3, "problem": "ExpectingInt" }
+        ])))
+        .unwrap();
+        assert_eq!(output.unwrap_err()[0].col, 3);
+    }
+
+    #[test]
+    fn declarations_added_once() {
+        let mut dependencies = ElmDependencies::default();
+        parser_input(&mut dependencies).unwrap();
+        parser_input(&mut dependencies).unwrap();
+        assert_eq!(
+            dependencies
+                .declarations()
+                .matches("problemToString :")
+                .count(),
+            1
+        );
+    }
+}
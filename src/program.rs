@@ -0,0 +1,473 @@
+use std::{marker::PhantomData, time::Duration};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[cfg(feature = "quickjs")]
+use crate::quickjs::ProgramModule;
+#[cfg(feature = "v8")]
+use crate::v8::ProgramModule;
+use crate::{clock::millis, error::Result, query::event_target, HtmlNode, PortType};
+
+/// A message sent by an Elm program through one of its own outgoing ports.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct PortMessage {
+    /// The name of the port, e.g. `saveSettings`
+    pub port: String,
+    pub value: Value,
+}
+
+/// A handle to a compiled Elm program, created via `ElmRoot::prepare_program`.
+/// Every call to `init` starts a new, independent instance of the program.
+pub struct ElmProgramHandle<Flags, Msg, Model> {
+    pub(crate) module: ProgramModule,
+    pub(crate) _type: PhantomData<(Flags, Msg, Model)>,
+}
+
+impl<Flags, Msg, Model> ElmProgramHandle<Flags, Msg, Model>
+where
+    Flags: Serialize,
+{
+    /// Starts an instance of the program by calling its `init` with the given flags.
+    #[cfg(feature = "v8")]
+    pub fn init(&self, flags: Flags) -> Result<ElmProgram<'_, Msg, Model>> {
+        let id = self.module.init(serde_json::to_value(flags)?)?;
+        Ok(ElmProgram {
+            module: &self.module,
+            id,
+            _type: PhantomData,
+        })
+    }
+
+    /// Starts an instance of the program by calling its `init` with the given flags.
+    #[cfg(feature = "quickjs")]
+    pub async fn init(&self, flags: Flags) -> Result<ElmProgram<'_, Msg, Model>> {
+        let id = self.module.init(serde_json::to_value(flags)?).await?;
+        Ok(ElmProgram {
+            module: &self.module,
+            id,
+            _type: PhantomData,
+        })
+    }
+}
+
+/// A running instance of an Elm program, which keeps its model between messages.
+///
+/// After every `send` (and after `init`), the commands of the program are run until no more ports are active,
+/// so the model and the port messages reflect all of their effects.
+///
+/// The instance is removed from the JS runtime when the program is dropped (with the `v8` feature)
+/// or via `dispose` (with the `quickjs` feature, since dropping can not wait for the JS runtime).
+pub struct ElmProgram<'a, Msg, Model> {
+    pub(crate) module: &'a ProgramModule,
+    pub(crate) id: usize,
+    pub(crate) _type: PhantomData<(Msg, Model)>,
+}
+
+impl<Msg, Model> ElmProgram<'_, Msg, Model>
+where
+    Msg: Serialize,
+{
+    /// Passes the message to the `update` function of the program.
+    #[cfg(feature = "v8")]
+    pub fn send(&self, msg: Msg) -> Result<()> {
+        self.module.call::<()>(
+            "sendPort",
+            vec![
+                self.id.into(),
+                "bindingSend".into(),
+                serde_json::to_value(msg)?,
+            ],
+        )?;
+        self.module.serve_http(self.id)
+    }
+
+    /// Passes the message to the `update` function of the program.
+    #[cfg(feature = "quickjs")]
+    pub async fn send(&self, msg: Msg) -> Result<()> {
+        self.module
+            .call::<()>(
+                "sendPort",
+                vec![
+                    self.id.into(),
+                    "bindingSend".into(),
+                    serde_json::to_value(msg)?,
+                ],
+            )
+            .await?;
+        self.module.serve_http(self.id).await
+    }
+}
+
+impl<Msg, Model> ElmProgram<'_, Msg, Model>
+where
+    Model: DeserializeOwned,
+{
+    /// The current model of the program.
+    #[cfg(feature = "v8")]
+    pub fn model(&self) -> Result<Model> {
+        self.module.call("model", vec![self.id.into()])
+    }
+
+    /// The current model of the program.
+    #[cfg(feature = "quickjs")]
+    pub async fn model(&self) -> Result<Model> {
+        self.module.call("model", vec![self.id.into()]).await
+    }
+}
+
+impl<Msg, Model> ElmProgram<'_, Msg, Model> {
+    /// Lets the given amount of time pass on the virtual clock of the program, running its timers
+    /// (e.g. of `Process.sleep` or `Time.every`) in order.
+    #[cfg(feature = "v8")]
+    pub fn advance(&self, duration: Duration) -> Result<()> {
+        self.module
+            .call::<()>("advance", vec![self.id.into(), millis(duration).into()])?;
+        self.module.serve_http(self.id)
+    }
+
+    /// Lets the given amount of time pass on the virtual clock of the program, running its timers
+    /// (e.g. of `Process.sleep` or `Time.every`) in order.
+    #[cfg(feature = "quickjs")]
+    pub async fn advance(&self, duration: Duration) -> Result<()> {
+        self.module
+            .call::<()>("advance", vec![self.id.into(), millis(duration).into()])
+            .await?;
+        self.module.serve_http(self.id).await
+    }
+
+    /// Returns the messages sent through the ports of the program since the last call, in the order they were sent.
+    #[cfg(feature = "v8")]
+    pub fn take_port_messages(&self) -> Result<Vec<PortMessage>> {
+        self.module.call("takePortMessages", vec![self.id.into()])
+    }
+
+    /// Returns the messages sent through the ports of the program since the last call, in the order they were sent.
+    #[cfg(feature = "quickjs")]
+    pub async fn take_port_messages(&self) -> Result<Vec<PortMessage>> {
+        self.module
+            .call("takePortMessages", vec![self.id.into()])
+            .await
+    }
+
+    /// Removes the instance of the program from the JS runtime, which keeps it (and its model) until then.
+    #[cfg(feature = "quickjs")]
+    pub async fn dispose(self) -> Result<()> {
+        self.module
+            .call::<()>("dispose", vec![self.id.into()])
+            .await
+    }
+}
+
+#[cfg(feature = "v8")]
+impl<Msg, Model> Drop for ElmProgram<'_, Msg, Model> {
+    fn drop(&mut self) {
+        // Errors can not be reported while dropping, and the instance is not used anymore anyway
+        let _ = self.module.call::<()>("dispose", vec![self.id.into()]);
+    }
+}
+
+/// Interaction with the rendered view of programs created via `ElmRoot::prepare_element`, `ElmRoot::prepare_sandbox`
+/// or `ElmRoot::prepare_application` (whose view is the whole body).
+/// Events are dispatched to the only element matching a selector (see `Selection`) and bubble up through its ancestors,
+/// so the messages are produced by the event handlers of the view, e.g. `Html.Events.onClick`.
+impl<Msg, Model> ElmProgram<'_, Msg, Model> {
+    /// The currently rendered view of the program.
+    #[cfg(feature = "v8")]
+    pub fn html(&self) -> Result<HtmlNode> {
+        self.module.call("html", vec![self.id.into()])
+    }
+
+    /// The currently rendered view of the program.
+    #[cfg(feature = "quickjs")]
+    pub async fn html(&self) -> Result<HtmlNode> {
+        self.module.call("html", vec![self.id.into()]).await
+    }
+
+    /// Clicks the element matching the selector, e.g. `button.save`.
+    #[cfg(feature = "v8")]
+    pub fn click(&self, selector: &str) -> Result<()> {
+        self.dispatch(selector, "click", json!({}))
+    }
+
+    /// Clicks the element matching the selector, e.g. `button.save`.
+    #[cfg(feature = "quickjs")]
+    pub async fn click(&self, selector: &str) -> Result<()> {
+        self.dispatch(selector, "click", json!({})).await
+    }
+
+    /// Sets the value of the input matching the selector and dispatches an `input` event, as typing into it would.
+    #[cfg(feature = "v8")]
+    pub fn input(&self, selector: &str, value: &str) -> Result<()> {
+        self.dispatch(selector, "input", json!({ "value": value }))
+    }
+
+    /// Sets the value of the input matching the selector and dispatches an `input` event, as typing into it would.
+    #[cfg(feature = "quickjs")]
+    pub async fn input(&self, selector: &str, value: &str) -> Result<()> {
+        self.dispatch(selector, "input", json!({ "value": value }))
+            .await
+    }
+
+    /// Submits the form matching the selector.
+    #[cfg(feature = "v8")]
+    pub fn submit(&self, selector: &str) -> Result<()> {
+        self.dispatch(selector, "submit", json!({}))
+    }
+
+    /// Submits the form matching the selector.
+    #[cfg(feature = "quickjs")]
+    pub async fn submit(&self, selector: &str) -> Result<()> {
+        self.dispatch(selector, "submit", json!({})).await
+    }
+
+    #[cfg(feature = "v8")]
+    fn dispatch(&self, selector: &str, event: &str, properties: Value) -> Result<()> {
+        let path = event_target(&self.html()?, selector)?;
+        self.module.call::<()>(
+            "dispatch",
+            vec![self.id.into(), path.into(), event.into(), properties],
+        )?;
+        self.module.serve_http(self.id)
+    }
+
+    #[cfg(feature = "quickjs")]
+    async fn dispatch(&self, selector: &str, event: &str, properties: Value) -> Result<()> {
+        let path = event_target(&self.html().await?, selector)?;
+        self.module
+            .call::<()>(
+                "dispatch",
+                vec![self.id.into(), path.into(), event.into(), properties],
+            )
+            .await?;
+        self.module.serve_http(self.id).await
+    }
+}
+
+/// Wraps an `update` function into a function applying a list of messages to an initial model,
+/// which returns the model after each message.
+pub(crate) fn fold_update(update: &str) -> String {
+    format!(
+        "\\model msgs -> List.foldl (\\msg ( current, models ) -> Tuple.first ({update} msg current) |> (\\next -> ( next, next :: models ))) ( model, [] ) msgs |> Tuple.second |> List.reverse"
+    )
+}
+
+/// The functions an Elm program module exposes, which determine how the generated program drives them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ProgramKind {
+    /// `init`, `update` and `subscriptions`, run as a `Platform.worker`
+    Worker,
+    /// `init`, `update`, `subscriptions` and `view` as for `Browser.element`
+    Element,
+    /// `init` (the initial model), `update` (returning only the model) and `view` as for `Browser.sandbox`
+    Sandbox,
+    /// `init`, `update`, `subscriptions`, `view`, `onUrlRequest` and `onUrlChange` as for `Browser.application`.
+    /// Neither messages nor the model are passed between Rust and the application.
+    Application,
+}
+
+/// Generates a program driving the `init`, `update` and `subscriptions` (and `view`) functions of the given module.
+/// Messages are received through the `bindingSend` port and every new model is sent through the `bindingModel` port.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn program_elm(
+    file_name: &str,
+    module: &str,
+    kind: ProgramKind,
+    imports: &str,
+    declarations: &str,
+    flags: &PortType,
+    msg: &PortType,
+    model: &PortType,
+) -> String {
+    let decode_msg = match (kind, msg) {
+        (ProgramKind::Application, _) => {
+            "(always (Invalid \"Applications do not receive messages from Rust\"))".to_owned()
+        }
+        (_, PortType::Native(_)) => "Update".to_owned(),
+        (_, PortType::Codec { decoder, .. }) => {
+            format!("({} >> toBindingMsg)", crate::decode_value(decoder))
+        }
+    };
+    let encode_model = match (kind, model) {
+        (ProgramKind::Application, _) => "always ()",
+        (_, PortType::Native(_)) => "identity",
+        (_, PortType::Codec { encoder, .. }) => encoder,
+    };
+    let (init, update, subscriptions) = match kind {
+        ProgramKind::Worker | ProgramKind::Element => (
+            format!("{module}.init"),
+            format!("{module}.update"),
+            format!("{module}.subscriptions"),
+        ),
+        ProgramKind::Sandbox => (
+            format!("(\\() -> ( {module}.init, Cmd.none ))"),
+            format!("(\\msg model -> ( {module}.update msg model, Cmd.none ))"),
+            "(\\_ -> Sub.none)".to_owned(),
+        ),
+        ProgramKind::Application => (
+            format!("(\\decodedFlags -> {module}.init decodedFlags url key)"),
+            format!("{module}.update"),
+            format!("{module}.subscriptions"),
+        ),
+    };
+    let (main, view) = match kind {
+        ProgramKind::Worker => (WORKER_MAIN, ""),
+        ProgramKind::Element | ProgramKind::Sandbox => (ELEMENT_MAIN, ELEMENT_VIEW),
+        ProgramKind::Application => (APPLICATION_MAIN, APPLICATION_VIEW),
+    };
+    PROGRAM_TEMPLATE
+        .replace("{{ file_name }}", file_name)
+        .replace("{{ imports }}", imports)
+        .replace("{{ declarations }}", declarations)
+        .replace("{{ main }}", main)
+        .replace("{{ view }}", view)
+        .replace("{{ init }}", &init)
+        .replace("{{ module }}", module)
+        .replace("{{ update }}", &update)
+        .replace("{{ subscriptions }}", &subscriptions)
+        .replace("{{ model_type }}", model.output_annotation())
+        .replace("{{ msg_type }}", msg.input_annotation())
+        .replace("{{ decode_flags }}", &decode_flags(flags))
+        .replace("{{ decode_msg }}", &decode_msg)
+        .replace("{{ encode_model }}", encode_model)
+}
+
+/// Decodes the `flags` of a generated program into a `Result String flags`.
+pub(crate) fn decode_flags(flags: &PortType) -> String {
+    match flags {
+        PortType::Native(_) => "Ok flags".to_owned(),
+        PortType::Codec { decoder, .. } => format!(
+            "Json.Decode.decodeValue {decoder} flags |> Result.mapError Json.Decode.errorToString"
+        ),
+    }
+}
+
+const PROGRAM_TEMPLATE: &str = include_str!("./templates/Program.elm.template");
+
+const WORKER_MAIN: &str = "Platform.worker
+        { init = init {{ init }}
+        , update = update
+        , subscriptions = subscriptions
+        }";
+
+const ELEMENT_MAIN: &str = "Browser.element
+        { init = init {{ init }}
+        , update = update
+        , subscriptions = subscriptions
+        , view = view
+        }";
+
+const ELEMENT_VIEW: &str = "
+view model =
+    case model of
+        Just programModel ->
+            Html.map Update ({{ module }}.view programModel)
+
+        Nothing ->
+            Html.text \"\"
+";
+
+const APPLICATION_MAIN: &str = "Browser.application
+        { init = \\flags url key -> init {{ init }} flags
+        , update = update
+        , subscriptions = subscriptions
+        , view = view
+        , onUrlRequest = {{ module }}.onUrlRequest >> Update
+        , onUrlChange = {{ module }}.onUrlChange >> Update
+        }";
+
+const APPLICATION_VIEW: &str = "
+view model =
+    case model of
+        Just programModel ->
+            let
+                document =
+                    {{ module }}.view programModel
+            in
+            { title = document.title, body = List.map (Html.map Update) document.body }
+
+        Nothing ->
+            { title = \"\", body = [] }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_update, program_elm, ProgramKind};
+    use crate::PortType;
+
+    #[test]
+    fn codec_msg() {
+        let elm = program_elm(
+            "Counter_Program1",
+            "Counter",
+            ProgramKind::Worker,
+            "import Counter",
+            "",
+            &PortType::Native("()".to_owned()),
+            &PortType::Codec {
+                decoder: "Counter.msgDecoder".to_owned(),
+                encoder: "Counter.encodeMsg".to_owned(),
+            },
+            &PortType::Native("{ count : Int }".to_owned()),
+        );
+        assert!(elm.starts_with("port module Counter_Program1 exposing (..)\n\nimport Counter\n"));
+        assert!(elm.contains("port bindingModel : { count : Int } -> Cmd msg"));
+        assert!(elm.contains("port bindingSend : (Json.Decode.Value -> msg) -> Sub msg"));
+        assert!(elm.contains("case Ok flags of"));
+        assert!(elm.contains("[ bindingSend (Json.Decode.decodeValue Counter.msgDecoder >> Result.mapError Json.Decode.errorToString >> toBindingMsg)"));
+        assert!(elm.contains("bindingModel (identity programModel)"));
+        assert!(elm.contains("    Platform.worker\n        { init = init Counter.init\n"));
+        assert!(!elm.contains("view"));
+    }
+
+    #[test]
+    fn sandbox() {
+        let elm = program_elm(
+            "Form_Program1",
+            "Form",
+            ProgramKind::Sandbox,
+            "import Browser\nimport Form\nimport Html",
+            "",
+            &PortType::Native("()".to_owned()),
+            &PortType::Native("Form.Msg".to_owned()),
+            &PortType::Native("Form.Model".to_owned()),
+        );
+        assert!(elm.contains(
+            "    Browser.element\n        { init = init (\\() -> ( Form.init, Cmd.none ))\n"
+        ));
+        assert!(elm.contains(
+            "(\\msg model -> ( Form.update msg model, Cmd.none )) programMsg programModel |> step"
+        ));
+        assert!(elm.contains("Maybe.map ((\\_ -> Sub.none) >> Sub.map Update)"));
+        assert!(elm.contains("Html.map Update (Form.view programModel)"));
+    }
+
+    #[test]
+    fn application() {
+        let elm = program_elm(
+            "Shop_Program1",
+            "Shop",
+            ProgramKind::Application,
+            "import Browser\nimport Html\nimport Shop",
+            "",
+            &PortType::Native("()".to_owned()),
+            &PortType::Native("()".to_owned()),
+            &PortType::Native("()".to_owned()),
+        );
+        assert!(elm.contains("{ init = \\flags url key -> init (\\decodedFlags -> Shop.init decodedFlags url key) flags\n"));
+        assert!(elm.contains(", onUrlChange = Shop.onUrlChange >> Update\n"));
+        assert!(elm.contains("body = List.map (Html.map Update) document.body"));
+        assert!(elm.contains("bindingModel (always () programModel)"));
+        assert!(elm.contains(
+            "bindingSend (always (Invalid \"Applications do not receive messages from Rust\"))"
+        ));
+    }
+
+    #[test]
+    fn fold_update_expression() {
+        assert_eq!(
+            fold_update("Page.update"),
+            "\\model msgs -> List.foldl (\\msg ( current, models ) -> Tuple.first (Page.update msg current) |> (\\next -> ( next, next :: models ))) ( model, [] ) msgs |> Tuple.second |> List.reverse"
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::{
+    error::{Error, Result},
+    HtmlElement, HtmlNode,
+};
+
+/// The elements of rendered Html matching a selector, in document order. Created via `HtmlNode::find`.
+///
+/// Selectors support tag names, `*`, `.class`, `#id`, `[attribute]` and `[attribute="value"]`,
+/// combined with the descendant (` `) and child (`>`) combinators and grouped with `,`,
+/// e.g. `nav > a.active, button[type="submit"]`.
+#[derive(Debug, Clone)]
+pub struct Selection<'a> {
+    selected: Vec<Selected<'a>>,
+}
+
+#[derive(Debug, Clone)]
+struct Selected<'a> {
+    element: &'a HtmlElement,
+    /// The ancestors of the element, starting at the root of the rendered Html
+    ancestors: Vec<&'a HtmlElement>,
+}
+
+impl HtmlNode {
+    /// Finds the elements matching the selector, including this node itself.
+    pub fn find(&self, selector: &str) -> Result<Selection<'_>> {
+        let selector = parse_selector(selector)?;
+        let mut selected = Vec::new();
+        if let HtmlNode::Element(element) = self {
+            select(&selector, element, &mut Vec::new(), &mut selected);
+        }
+        Ok(Selection { selected })
+    }
+
+    /// The text content of the node, i.e. all of its text nodes concatenated.
+    pub fn text(&self) -> String {
+        match self {
+            HtmlNode::Element(element) => element.text(),
+            HtmlNode::Text(text) => text.clone(),
+        }
+    }
+}
+
+impl HtmlElement {
+    /// The text content of the element, i.e. all of its text nodes concatenated.
+    pub fn text(&self) -> String {
+        self.children.iter().map(HtmlNode::text).collect()
+    }
+
+    /// The value of the given attribute, if the element has it.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+}
+
+impl<'a> Selection<'a> {
+    /// Finds the descendants of the selected elements matching the selector.
+    pub fn find(&self, selector: &str) -> Result<Selection<'a>> {
+        let selector = parse_selector(selector)?;
+        let mut selected: Vec<Selected<'a>> = Vec::new();
+        for Selected { element, ancestors } in &self.selected {
+            let mut ancestors = ancestors.clone();
+            ancestors.push(element);
+            for child in &element.children {
+                if let HtmlNode::Element(child) = child {
+                    select(&selector, child, &mut ancestors, &mut selected);
+                }
+            }
+        }
+        // Elements nested in several selected elements are only selected once
+        let mut unique: Vec<Selected<'a>> = Vec::with_capacity(selected.len());
+        for candidate in selected {
+            if !unique
+                .iter()
+                .any(|other| std::ptr::eq(other.element, candidate.element))
+            {
+                unique.push(candidate);
+            }
+        }
+        Ok(Selection { selected: unique })
+    }
+
+    /// The number of selected elements.
+    pub fn count(&self) -> usize {
+        self.selected.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    /// The text content of all selected elements, concatenated.
+    pub fn text(&self) -> String {
+        self.iter().map(HtmlElement::text).collect()
+    }
+
+    /// The value of the given attribute of the first selected element.
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.first()?.attribute(name)
+    }
+
+    pub fn first(&self) -> Option<&'a HtmlElement> {
+        self.selected.first().map(|selected| selected.element)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a HtmlElement> + '_ {
+        self.selected.iter().map(|selected| selected.element)
+    }
+}
+
+/// The path (of child indices) from the root of the rendered Html to the only element matching the selector,
+/// which events are dispatched to.
+pub(crate) fn event_target(html: &HtmlNode, selector: &str) -> Result<Vec<usize>> {
+    let selection = html.find(selector)?;
+    let [Selected { element, ancestors }] = selection.selected.as_slice() else {
+        return Err(Box::new(Error::NoUniqueElement {
+            selector: selector.to_owned(),
+            count: selection.count(),
+        }));
+    };
+    let descendants = ancestors.iter().skip(1).chain([element]);
+    Ok(ancestors
+        .iter()
+        .zip(descendants)
+        .map(|(parent, child)| {
+            parent
+                .children
+                .iter()
+                .position(
+                    |node| matches!(node, HtmlNode::Element(other) if std::ptr::eq(other, *child)),
+                )
+                .unwrap_or_default()
+        })
+        .collect())
+}
+
+/// Adds the element and its descendants to the selection if they match, in document order.
+fn select<'a>(
+    selector: &[ComplexSelector],
+    element: &'a HtmlElement,
+    ancestors: &mut Vec<&'a HtmlElement>,
+    selected: &mut Vec<Selected<'a>>,
+) {
+    if selector
+        .iter()
+        .any(|complex| complex.matches(element, ancestors))
+    {
+        selected.push(Selected {
+            element,
+            ancestors: ancestors.clone(),
+        });
+    }
+    ancestors.push(element);
+    for child in &element.children {
+        if let HtmlNode::Element(child) = child {
+            select(selector, child, ancestors, selected);
+        }
+    }
+    ancestors.pop();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// Compound selectors joined by combinators, e.g. `nav > a.active`.
+/// Each compound selector is stored with the combinator preceding it.
+#[derive(Debug, PartialEq)]
+struct ComplexSelector(Vec<(Combinator, CompoundSelector)>);
+
+/// The conditions a single element has to fulfill, e.g. `a.active[href]`.
+#[derive(Debug, Default, PartialEq)]
+struct CompoundSelector {
+    tag: Option<String>,
+    ids: Vec<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl ComplexSelector {
+    fn matches(&self, element: &HtmlElement, ancestors: &[&HtmlElement]) -> bool {
+        matches_compounds(&self.0, element, ancestors)
+    }
+}
+
+/// Matches from right to left: the last compound selector has to match the element itself,
+/// the ones before it the parent (`>`) or any ancestor (` `).
+fn matches_compounds(
+    compounds: &[(Combinator, CompoundSelector)],
+    element: &HtmlElement,
+    ancestors: &[&HtmlElement],
+) -> bool {
+    let Some(((combinator, last), rest)) = compounds.split_last() else {
+        return true;
+    };
+    if !last.matches(element) {
+        return false;
+    }
+    if rest.is_empty() {
+        return true;
+    }
+    match combinator {
+        Combinator::Child => ancestors
+            .split_last()
+            .is_some_and(|(parent, above)| matches_compounds(rest, parent, above)),
+        Combinator::Descendant => (0..ancestors.len())
+            .rev()
+            .any(|index| matches_compounds(rest, ancestors[index], &ancestors[..index])),
+    }
+}
+
+impl CompoundSelector {
+    fn matches(&self, element: &HtmlElement) -> bool {
+        let classes = element.attribute("class").unwrap_or_default();
+        self.tag
+            .as_ref()
+            .is_none_or(|tag| tag.eq_ignore_ascii_case(&element.tag))
+            && self
+                .ids
+                .iter()
+                .all(|id| element.attribute("id") == Some(id.as_str()))
+            && self
+                .classes
+                .iter()
+                .all(|class| classes.split_whitespace().any(|other| other == class))
+            && self
+                .attributes
+                .iter()
+                .all(|(name, value)| match (element.attribute(name), value) {
+                    (Some(actual), Some(expected)) => actual == expected,
+                    (actual, None) => actual.is_some(),
+                    (None, Some(_)) => false,
+                })
+    }
+
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Vec<ComplexSelector>> {
+    split_groups(selector)
+        .into_iter()
+        .map(|complex| {
+            parse_complex(complex)
+                .ok_or_else(|| Box::new(Error::InvalidSelector(selector.to_owned())))
+        })
+        .collect()
+}
+
+/// Splits a selector group at the commas outside of attribute selectors and quoted values.
+fn split_groups(selector: &str) -> Vec<&str> {
+    let mut groups = Vec::new();
+    let mut start = 0;
+    let mut in_brackets = false;
+    let mut quote = None;
+    for (index, char) in selector.char_indices() {
+        match (char, quote) {
+            (_, Some(open)) if char == open => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) if in_brackets => quote = Some(char),
+            ('[', None) => in_brackets = true,
+            (']', None) => in_brackets = false,
+            (',', None) if !in_brackets => {
+                groups.push(&selector[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    groups.push(&selector[start..]);
+    groups
+}
+
+fn parse_complex(selector: &str) -> Option<ComplexSelector> {
+    let mut chars = selector.trim().chars().peekable();
+    let mut compounds = Vec::new();
+    let mut combinator = Combinator::Descendant;
+    loop {
+        while chars.next_if(|char| char.is_whitespace()).is_some() {}
+        match chars.peek() {
+            None => break,
+            Some('>') if !compounds.is_empty() && combinator == Combinator::Descendant => {
+                chars.next();
+                combinator = Combinator::Child;
+            }
+            Some(_) => {
+                compounds.push((combinator, parse_compound(&mut chars)?));
+                combinator = Combinator::Descendant;
+            }
+        }
+    }
+    // A selector ending with `>` lacks the element the combinator refers to
+    (!compounds.is_empty() && combinator == Combinator::Descendant)
+        .then_some(ComplexSelector(compounds))
+}
+
+fn parse_compound(chars: &mut Peekable<Chars>) -> Option<CompoundSelector> {
+    let mut compound = CompoundSelector::default();
+    let universal = chars.next_if_eq(&'*').is_some();
+    if !universal && chars.peek().is_some_and(|char| is_name_char(*char)) {
+        compound.tag = Some(parse_name(chars)?);
+    }
+    loop {
+        match chars.peek() {
+            None => break,
+            Some(char) if char.is_whitespace() || *char == '>' => break,
+            Some('.') => {
+                chars.next();
+                compound.classes.push(parse_name(chars)?);
+            }
+            Some('#') => {
+                chars.next();
+                compound.ids.push(parse_name(chars)?);
+            }
+            Some('[') => {
+                chars.next();
+                compound.attributes.push(parse_attribute(chars)?);
+            }
+            Some(_) => return None,
+        }
+    }
+    (universal || !compound.is_empty()).then_some(compound)
+}
+
+/// Parses `name]`, `name=value]` or `name="value"]` (after the opening bracket).
+fn parse_attribute(chars: &mut Peekable<Chars>) -> Option<(String, Option<String>)> {
+    let name = parse_name(chars)?;
+    match chars.next()? {
+        ']' => Some((name, None)),
+        '=' => {
+            let value = match chars.next_if(|char| *char == '"' || *char == '\'') {
+                Some(quote) => {
+                    let value = chars.by_ref().take_while(|char| *char != quote).collect();
+                    chars.next_if_eq(&']')?;
+                    value
+                }
+                None => {
+                    let mut value = String::new();
+                    while let Some(char) = chars.next_if(|char| *char != ']') {
+                        value.push(char);
+                    }
+                    chars.next_if_eq(&']')?;
+                    value
+                }
+            };
+            Some((name, Some(value)))
+        }
+        _ => None,
+    }
+}
+
+fn parse_name(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut name = String::new();
+    while let Some(char) = chars.next_if(|char| is_name_char(*char)) {
+        name.push(char);
+    }
+    (!name.is_empty()).then_some(name)
+}
+
+fn is_name_char(char: char) -> bool {
+    char.is_alphanumeric() || char == '-' || char == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::event_target;
+    use crate::{Error, HtmlNode};
+
+    fn page() -> HtmlNode {
+        serde_json::from_value(json!({
+            "element": { "tag": "div", "attributes": { "id": "app" }, "children": [
+                { "element": { "tag": "nav", "attributes": {}, "children": [
+                    { "element": { "tag": "a", "attributes": { "href": "/", "class": "link active" }, "children": [{ "text": "Home" }] } },
+                    { "element": { "tag": "span", "attributes": {}, "children": [
+                        { "element": { "tag": "a", "attributes": { "href": "/about", "class": "link" }, "children": [{ "text": "About" }] } }
+                    ] } }
+                ] } },
+                { "element": { "tag": "button", "attributes": { "class": "primary", "type": "submit" }, "children": [
+                    { "text": "Save " }, { "element": { "tag": "b", "attributes": {}, "children": [{ "text": "now" }] } }
+                ] } }
+            ] }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn find_elements() {
+        let page = page();
+        assert_eq!(page.find("div#app").unwrap().count(), 1);
+        assert_eq!(page.find("a.link").unwrap().count(), 2);
+        assert_eq!(page.find("nav > a").unwrap().text(), "Home");
+        assert_eq!(page.find("a.active").unwrap().attribute("href"), Some("/"));
+        assert_eq!(
+            page.find("#app a[href=\"/about\"]").unwrap().text(),
+            "About"
+        );
+        assert_eq!(page.find("button[type=submit]").unwrap().text(), "Save now");
+        assert_eq!(page.find("b, nav > *").unwrap().count(), 3);
+        assert_eq!(
+            page.find("a[class=\"link, active\"], b").unwrap().count(),
+            1
+        );
+        assert_eq!(page.find("a[class='link active'],b").unwrap().count(), 2);
+        assert!(page.find("button.secondary").unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_within_selection() {
+        let page = page();
+        let nav = page.find("nav").unwrap();
+        assert_eq!(nav.find("a").unwrap().count(), 2);
+        assert_eq!(nav.find("div a").unwrap().count(), 2);
+        assert!(nav.find("nav").unwrap().is_empty());
+        // Links nested in both selected elements are found once
+        assert_eq!(page.find("*").unwrap().find("a").unwrap().count(), 2);
+    }
+
+    #[test]
+    fn event_target_path() {
+        let page = page();
+        assert_eq!(event_target(&page, "#app").unwrap(), Vec::<usize>::new());
+        assert_eq!(event_target(&page, "a[href='/about']").unwrap(), [0, 1, 0]);
+        assert_eq!(event_target(&page, "button b").unwrap(), [1, 1]);
+        assert!(matches!(
+            *event_target(&page, "a").unwrap_err(),
+            Error::NoUniqueElement { count: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_selectors() {
+        for selector in [
+            "",
+            "a >",
+            "> a",
+            "a >> b",
+            "a:hover",
+            "[href",
+            "a[href=foo",
+            "a[href='/",
+            "a,",
+            ".",
+        ] {
+            assert!(
+                matches!(
+                    *page().find(selector).unwrap_err(),
+                    Error::InvalidSelector(_)
+                ),
+                "{selector}"
+            );
+        }
+    }
+}
@@ -4,7 +4,12 @@ use std::{
     sync::{Arc, LazyLock, RwLock},
 };
 
-use crate::{error::Result, ElmBinding, TO_ESM_JS};
+use crate::{
+    clock::ClockSettings,
+    error::Result,
+    http::{self, CallStep, HttpService},
+    with_browser_shims, ElmBinding, TO_ESM_JS,
+};
 use quickjs_runtime::{
     builder::QuickJsRuntimeBuilder,
     facades::QuickJsRuntimeFacade,
@@ -13,6 +18,7 @@ use quickjs_runtime::{
     values::{JsValueConvertable, JsValueFacade},
 };
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
 use crate::ElmRoot;
 
@@ -25,7 +31,9 @@ static RUNTIME: LazyLock<QuickJsRuntimeFacade> = LazyLock::new(|| {
 });
 
 pub struct ElmFunctionHandle<I, O> {
-    function_name: String,
+    binding_module_name: String,
+    clock: ClockSettings,
+    http: Option<HttpService>,
     _type: PhantomData<(I, O)>,
 }
 
@@ -33,6 +41,53 @@ pub async fn prepare<I, O>(
     root: &ElmRoot,
     elm_binding: ElmBinding,
 ) -> Result<ElmFunctionHandle<I, O>> {
+    let ElmBinding {
+        compiled_binding,
+        binding_module_name,
+    } = elm_binding;
+    let esm_compiled_binding = to_esm(root, &binding_module_name, compiled_binding).await?;
+
+    let define_global_function = Script::new(
+        &binding_module_name,
+        &RUN_JS_TEMPLATE.replace("{{ binding_module_name }}", &binding_module_name),
+    );
+    LOADER.register(format!("{binding_module_name}.js"), esm_compiled_binding);
+    RUNTIME.eval(None, define_global_function).await?;
+
+    Ok(ElmFunctionHandle {
+        binding_module_name,
+        clock: root.clock,
+        http: root.http.clone(),
+        _type: PhantomData,
+    })
+}
+
+/// Loads a compiled `Platform.worker` wrapping an Elm program, see `ProgramModule`.
+pub async fn prepare_program(root: &ElmRoot, elm_binding: ElmBinding) -> Result<ProgramModule> {
+    let ElmBinding {
+        compiled_binding,
+        binding_module_name,
+    } = elm_binding;
+    let esm_compiled_binding = to_esm(root, &binding_module_name, compiled_binding).await?;
+    let define_global_functions = Script::new(
+        &binding_module_name,
+        &PROGRAM_JS_TEMPLATE.replace("{{ binding_module_name }}", &binding_module_name),
+    );
+    LOADER.register(format!("{binding_module_name}.js"), esm_compiled_binding);
+    RUNTIME.eval(None, define_global_functions).await?;
+    Ok(ProgramModule {
+        binding_module_name,
+        clock: root.clock,
+        http: root.http.clone(),
+    })
+}
+
+/// Makes the compiled JS esm compatible
+async fn to_esm(
+    root: &ElmRoot,
+    binding_module_name: &str,
+    compiled_binding: String,
+) -> Result<String> {
     LOADER.register("to-esm.js", TO_ESM_JS);
     RUNTIME
         .eval(
@@ -48,29 +103,11 @@ async function toEsm(str) {
             ),
         )
         .await?;
-    let args = vec![elm_binding.compiled_binding.to_js_value_facade()];
+    let args = vec![compiled_binding.to_js_value_facade()];
     let result = invoke_function("toEsm", args).await?;
-    let esm_compiled_binding = result.get_str();
-    root.write_esm_binding(&elm_binding.binding_module_name, esm_compiled_binding)?;
-
-    let define_global_function = Script::new(
-        &elm_binding.binding_module_name,
-        &RUN_JS_TEMPLATE.replace(
-            "{{ binding_module_name }}",
-            &elm_binding.binding_module_name,
-        ),
-    );
-    LOADER.register(
-        format!("{}.js", elm_binding.binding_module_name),
-        esm_compiled_binding,
-    );
-    RUNTIME.eval(None, define_global_function).await?;
-    let function_name = format!("call_{}", elm_binding.binding_module_name);
-
-    Ok(ElmFunctionHandle {
-        function_name,
-        _type: PhantomData,
-    })
+    let esm_compiled_binding = with_browser_shims(result.get_str());
+    root.write_esm_binding(binding_module_name, &esm_compiled_binding)?;
+    Ok(esm_compiled_binding)
 }
 
 impl<I, O> ElmFunctionHandle<I, O>
@@ -81,8 +118,96 @@ where
     /// Calls the elm function with the given input and return the output.
     pub async fn call(&self, input: I) -> Result<O> {
         let flags = serde_json::to_value(input)?;
-        let args = vec![flags.to_js_value_facade()];
-        let return_value_facade = invoke_function(&self.function_name, args).await?;
+        let args = vec![flags, serde_json::to_value(self.clock)?];
+        let mut step = self.invoke("call", args).await?;
+        // The call is continued with the answers to its Http requests until it produced its output
+        loop {
+            match step {
+                CallStep::Done { output } => return Ok(serde_json::from_value(output)?),
+                CallStep::Pending { pending, requests } => {
+                    let responses = match http::serve(self.http.as_ref(), requests).await {
+                        Ok(responses) => responses,
+                        Err(error) => {
+                            // The call can not be continued, so the JS side forgets about it
+                            let function_name = format!("cancel_{}", self.binding_module_name);
+                            let args = vec![Value::from(pending).to_js_value_facade()];
+                            invoke_function(&function_name, args).await?;
+                            return Err(error);
+                        }
+                    };
+                    let args = vec![pending.into(), serde_json::to_value(responses)?];
+                    step = self.invoke("respond", args).await?;
+                }
+            }
+        }
+    }
+
+    async fn invoke(&self, function: &str, args: Vec<Value>) -> Result<CallStep> {
+        let args = args
+            .into_iter()
+            .map(JsValueConvertable::to_js_value_facade)
+            .collect();
+        let function_name = format!("{function}_{}", self.binding_module_name);
+        let return_value_facade = invoke_function(&function_name, args).await?;
+        let return_value = return_value_facade.to_serde_value().await?;
+        Ok(serde_json::from_value(return_value)?)
+    }
+}
+
+/// The global functions driving the running instances of an Elm program.
+/// They take the id of the instance returned by `init` as their first argument.
+pub struct ProgramModule {
+    binding_module_name: String,
+    clock: ClockSettings,
+    http: Option<HttpService>,
+}
+
+impl ProgramModule {
+    /// Starts a new instance of the program with its own virtual clock and returns its id.
+    pub async fn init(&self, flags: Value) -> Result<usize> {
+        self.start(flags, Value::Null).await
+    }
+
+    /// Starts a new instance of a `Browser.application` at the given url and returns its id.
+    pub async fn init_application(&self, flags: Value, url: &str) -> Result<usize> {
+        self.start(flags, url.into()).await
+    }
+
+    async fn start(&self, flags: Value, url: Value) -> Result<usize> {
+        let id = self
+            .call("init", vec![flags, serde_json::to_value(self.clock)?, url])
+            .await?;
+        self.serve_http(id).await?;
+        Ok(id)
+    }
+
+    /// Answers the Http requests of the instance, until it does not send any more.
+    pub async fn serve_http(&self, id: usize) -> Result<()> {
+        loop {
+            let requests: Vec<_> = self.call("takeHttpRequests", vec![id.into()]).await?;
+            if requests.is_empty() {
+                return Ok(());
+            }
+            let responses = http::serve(self.http.as_ref(), requests).await?;
+            self.call::<()>(
+                "respondHttp",
+                vec![id.into(), serde_json::to_value(responses)?],
+            )
+            .await?;
+        }
+    }
+
+    /// Calls one of the global functions of the program.
+    pub async fn call<O>(&self, function: &str, args: Vec<Value>) -> Result<O>
+    where
+        O: DeserializeOwned,
+    {
+        let args = args
+            .into_iter()
+            .map(JsValueConvertable::to_js_value_facade)
+            .collect();
+        let function_name = format!("{function}_{}", self.binding_module_name);
+        let return_value_facade = invoke_function(&function_name, args).await?;
         let return_value = return_value_facade.to_serde_value().await?;
         let output = serde_json::from_value(return_value)?;
         Ok(output)
@@ -90,6 +215,7 @@ where
 }
 
 const RUN_JS_TEMPLATE: &str = include_str!("./templates/run.qjs.template");
+const PROGRAM_JS_TEMPLATE: &str = include_str!("./templates/program.qjs.template");
 
 async fn invoke_function(name: &str, args: Vec<JsValueFacade>) -> Result<JsValueFacade> {
     let return_value = RUNTIME.invoke_function(None, &[], name, args).await?;
@@ -0,0 +1,66 @@
+use serde::de::DeserializeOwned;
+
+use crate::{error::Result, ElmDependencies, ElmFunctionHandle, PortType};
+
+/// A handle to an Elm `Random.Generator`, created via `ElmRoot::prepare_generator`.
+pub struct ElmGeneratorHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<(i32, usize), Vec<O>>,
+}
+
+impl<O> ElmGeneratorHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Generates `n` values, starting with `Random.initialSeed seed`.
+    /// The same seed always results in the same values, in the order they were generated by `Random.step`
+    /// (so the values for a smaller `n` are a prefix of the ones for a larger `n`).
+    #[cfg(feature = "v8")]
+    pub fn sample(&self, seed: i32, n: usize) -> Result<Vec<O>> {
+        self.handle.call((seed, n))
+    }
+
+    /// Generates `n` values, starting with `Random.initialSeed seed`.
+    /// The same seed always results in the same values, in the order they were generated by `Random.step`
+    /// (so the values for a smaller `n` are a prefix of the ones for a larger `n`).
+    #[cfg(feature = "quickjs")]
+    pub async fn sample(&self, seed: i32, n: usize) -> Result<Vec<O>> {
+        self.handle.call((seed, n)).await
+    }
+}
+
+/// Wraps a generator into a function taking the seed and the number of values to generate.
+pub(crate) fn sample_generator(generator: &str) -> String {
+    format!("\\seed n -> generateValues n {generator} (Random.initialSeed seed) []")
+}
+
+/// The seed and the number of values to generate, along with the declarations needed by `sample_generator`.
+pub(crate) fn sample_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Random");
+    declare_generate_values(dependencies);
+    Ok(vec![
+        PortType::Native("Int".to_owned()),
+        PortType::Native("Int".to_owned()),
+    ])
+}
+
+/// Declares `generateValues`, which steps a generator a number of times.
+/// The values are kept in the order they were generated (unlike with `Random.list`),
+/// so that the value at an index is the same for every count.
+pub(crate) fn declare_generate_values(dependencies: &mut ElmDependencies) {
+    if dependencies.declare("generateValues") {
+        dependencies.define("generateValues", GENERATE_VALUES.to_owned());
+    }
+}
+
+const GENERATE_VALUES: &str =
+    "generateValues : Int -> Random.Generator a -> Random.Seed -> List a -> List a
+generateValues count generator seed generated =
+    if count <= 0 then
+        List.reverse generated
+
+    else
+        let
+            ( value, nextSeed ) =
+                Random.step generator seed
+        in
+        generateValues (count - 1) generator nextSeed (value :: generated)";
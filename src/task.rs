@@ -0,0 +1,102 @@
+use std::{convert::identity, marker::PhantomData};
+
+use crate::{
+    elm_type::{self, PortType},
+    error::Result,
+    json::FallibleOutput,
+    ElmCompatible, ElmDependencies, ElmFunctionHandle,
+};
+use serde::de::DeserializeOwned;
+
+/// A handle to a function returning a `Task x a`, created via `ElmRoot::prepare_task`.
+/// Calling it performs the task on the virtual clock until it completes.
+pub struct ElmTaskHandle<I, O, X> {
+    pub(crate) handle: ElmFunctionHandle<I, FallibleOutput>,
+    pub(crate) _type: PhantomData<(O, X)>,
+}
+
+impl<I, O, X> ElmTaskHandle<I, O, X>
+where
+    I: serde::Serialize,
+    O: DeserializeOwned,
+    X: DeserializeOwned,
+{
+    /// Performs the task returned by the Elm function for the given input.
+    /// Returns the result of the task or the error it failed with.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, input: I) -> Result<std::result::Result<O, X>> {
+        let result = self.handle.call(input)?;
+        task_output(result)
+    }
+
+    /// Performs the task returned by the Elm function for the given input.
+    /// Returns the result of the task or the error it failed with.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, input: I) -> Result<std::result::Result<O, X>> {
+        let result = self.handle.call(input).await?;
+        task_output(result)
+    }
+}
+
+fn task_output<O: DeserializeOwned, X: DeserializeOwned>(
+    output: FallibleOutput,
+) -> Result<std::result::Result<O, X>> {
+    match output.into_result()? {
+        Ok(value) => Ok(Ok(serde_json::from_value(value)?)),
+        Err(error) => Ok(Err(error)),
+    }
+}
+
+/// The Elm error type of the binding and a function wrapping the task into one failing with that type.
+/// Errors with Json codecs are encoded before they are sent through the port.
+pub(crate) fn task_error<X: ElmCompatible>() -> Result<(String, impl FnOnce(&str) -> String)> {
+    let error_port_type = elm_type::port_type::<X>(identity, &mut ElmDependencies::default())?;
+    let error_type = error_port_type.output_annotation().to_owned();
+    Ok((error_type, move |task: &str| match error_port_type {
+        PortType::Native(_) => task.to_owned(),
+        PortType::Codec { encoder, .. } => format!("{task} >> Task.mapError {encoder}"),
+    }))
+}
+
+/// The argument of the function returning the task, along with the declarations of its error type.
+pub(crate) fn task_input<I: ElmCompatible, X: ElmCompatible>(
+    dependencies: &mut ElmDependencies,
+) -> Result<Vec<PortType>> {
+    dependencies.import("Task");
+    elm_type::port_type::<X>(identity, dependencies)?;
+    Ok(vec![elm_type::port_type::<I>(
+        elm_type::wrap_in_round_brackets,
+        dependencies,
+    )?])
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::task_output;
+    use crate::json::FallibleOutput;
+
+    #[test]
+    fn succeeded_task() {
+        let output: std::result::Result<i32, String> =
+            task_output(FallibleOutput::succeeded(json!(42))).unwrap();
+        assert_eq!(output, Ok(42));
+    }
+
+    #[test]
+    fn failed_task() {
+        let output: std::result::Result<i32, String> =
+            task_output(FallibleOutput::failed(json!("timeout"))).unwrap();
+        assert_eq!(output, Err("timeout".to_owned()));
+    }
+
+    #[test]
+    fn failed_task_with_null_error() {
+        let output: std::result::Result<Option<i32>, ()> = task_output(
+            serde_json::from_value(json!({ "ok": false, "error": null, "value": null })).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(output, Err(()));
+    }
+}
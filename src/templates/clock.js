@@ -0,0 +1,103 @@
+// A virtual clock, which replaces the timers, `Date` and `Math.random` of the compiled Elm code below.
+// Timers only run when the clock is driven, so time passes deterministically.
+export class VirtualClock {
+  constructor({ now, seed }) {
+    this.now = now;
+    this.seed = seed >>> 0;
+    this.timers = [];
+    this.sequence = 0;
+  }
+
+  setTimer(callback, delay, interval) {
+    const id = ++this.sequence;
+    const due = this.now + globalThis.Math.max(0, delay || 0);
+    this.timers.push({ id, callback, due, interval, order: id });
+    return id;
+  }
+
+  clearTimer(id) {
+    this.timers = this.timers.filter((timer) => timer.id !== id);
+  }
+
+  // Runs the next timer if it is due at or before `until`, moving the clock forward to its due time.
+  // Returns whether a timer was run.
+  runNext(until = Infinity) {
+    if (this.timers.length === 0) {
+      return false;
+    }
+    const next = this.timers.reduce((earliest, timer) =>
+      timer.due < earliest.due || (timer.due === earliest.due && timer.order < earliest.order)
+        ? timer
+        : earliest
+    );
+    if (next.due > until) {
+      return false;
+    }
+    this.now = globalThis.Math.max(this.now, next.due);
+    if (next.interval === undefined) {
+      this.clearTimer(next.id);
+    } else {
+      next.due += globalThis.Math.max(1, next.interval);
+      next.order = ++this.sequence;
+    }
+    next.callback();
+    return true;
+  }
+
+  // Runs all timers which are due now (including the ones they start), up to a limit against endless loops.
+  runDue() {
+    for (let steps = 0; this.runNext(this.now); steps++) {
+      if (steps >= 100000) {
+        throw new Error("Timers kept running without time passing");
+      }
+    }
+  }
+
+  // Runs all timers until `now + duration` in order and moves the clock to that point in time.
+  advance(duration) {
+    const until = this.now + duration;
+    while (this.runNext(until)) {}
+    this.now = until;
+    this.runDue();
+  }
+
+  // mulberry32
+  random() {
+    this.seed = (this.seed + 0x6d2b79f5) >>> 0;
+    let t = this.seed;
+    t = globalThis.Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + globalThis.Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  }
+}
+
+let virtualClock;
+
+// Sets the clock used by the Elm code until the next call.
+export function useClock(clock) {
+  virtualClock = clock;
+}
+
+const setTimeout = (callback, delay) => virtualClock.setTimer(callback, delay, undefined);
+const setInterval = (callback, delay) => virtualClock.setTimer(callback, delay, delay);
+const clearTimeout = (id) => virtualClock.clearTimer(id);
+const clearInterval = clearTimeout;
+
+class Date extends globalThis.Date {
+  constructor(...args) {
+    if (args.length === 0) {
+      super(virtualClock.now);
+    } else {
+      super(...args);
+    }
+  }
+
+  static now() {
+    return virtualClock.now;
+  }
+}
+
+const Math = Object.create(globalThis.Math, {
+  random: { value: () => virtualClock.random() },
+});
+
@@ -0,0 +1,273 @@
+// A minimal DOM, which the views of the compiled Elm code below are rendered into instead of a browser document.
+class DomNode {
+  #parent = null;
+  #children = [];
+
+  get parentNode() {
+    return this.#parent;
+  }
+
+  get childNodes() {
+    return this.#children;
+  }
+
+  get firstChild() {
+    return this.#children[0] ?? null;
+  }
+
+  appendChild(child) {
+    return this.insertBefore(child, null);
+  }
+
+  insertBefore(child, reference) {
+    if (child instanceof DocumentFragment) {
+      for (const fragmentChild of [...child.childNodes]) {
+        this.insertBefore(fragmentChild, reference);
+      }
+      return child;
+    }
+    child.#parent?.removeChild(child);
+    const index = reference ? this.#children.indexOf(reference) : -1;
+    if (index === -1) {
+      this.#children.push(child);
+    } else {
+      this.#children.splice(index, 0, child);
+    }
+    child.#parent = this;
+    return child;
+  }
+
+  removeChild(child) {
+    const index = this.#children.indexOf(child);
+    if (index !== -1) {
+      this.#children.splice(index, 1);
+      child.#parent = null;
+    }
+    return child;
+  }
+
+  replaceChild(newChild, oldChild) {
+    this.insertBefore(newChild, oldChild);
+    return this.removeChild(oldChild);
+  }
+}
+
+class Text extends DomNode {
+  constructor(data) {
+    super();
+    this.data = data;
+  }
+
+  get nodeType() {
+    return 3;
+  }
+
+  get textContent() {
+    return this.data;
+  }
+
+  get length() {
+    return this.data.length;
+  }
+
+  replaceData(offset, count, data) {
+    this.data = this.data.slice(0, offset) + data + this.data.slice(offset + count);
+  }
+}
+
+class DocumentFragment extends DomNode {
+  get nodeType() {
+    return 11;
+  }
+}
+
+// Properties Elm sets (e.g. via `Html.Attributes.class`) whose attribute has a different name
+const PROPERTY_ATTRIBUTES = {
+  className: "class",
+  htmlFor: "for",
+  httpEquiv: "http-equiv",
+  acceptCharset: "accept-charset",
+};
+
+class Element extends DomNode {
+  #tag;
+  #namespace;
+  #attributes = new Map();
+  #style = {};
+  #listeners = {};
+  #href;
+
+  constructor(tag, namespace) {
+    super();
+    this.#tag = tag;
+    this.#namespace = namespace;
+  }
+
+  get nodeType() {
+    return 1;
+  }
+
+  get tagName() {
+    return this.#tag.toUpperCase();
+  }
+
+  get localName() {
+    return this.#tag;
+  }
+
+  get namespaceURI() {
+    return this.#namespace;
+  }
+
+  get style() {
+    return this.#style;
+  }
+
+  get attributes() {
+    return [...this.#attributes].map(([name, value]) => ({ name, value }));
+  }
+
+  // Links resolve their `href` against the location of the application (see navigation.js), like in a browser.
+  get href() {
+    const href = this.#href || this.#attributes.get("href");
+    return href ? resolveUrl(href, currentUrl()) : "";
+  }
+
+  set href(href) {
+    this.#href = href;
+  }
+
+  hasAttribute(name) {
+    return this.#attributes.has(name);
+  }
+
+  setAttribute(name, value) {
+    this.#attributes.set(name, String(value));
+  }
+
+  removeAttribute(name) {
+    this.#attributes.delete(name);
+  }
+
+  setAttributeNS(_namespace, name, value) {
+    this.setAttribute(name, value);
+  }
+
+  removeAttributeNS(_namespace, name) {
+    this.removeAttribute(name);
+  }
+
+  addEventListener(type, listener) {
+    (this.#listeners[type] ??= []).push(listener);
+  }
+
+  removeEventListener(type, listener) {
+    this.#listeners[type] = (this.#listeners[type] ?? []).filter((other) => other !== listener);
+  }
+
+  // Calls the listeners of the element for the event, which reached it as its `currentTarget`.
+  callListeners(event) {
+    for (const listener of [...(this.#listeners[event.type] ?? [])]) {
+      listener(event);
+    }
+  }
+
+  // The attributes of the element as they would be serialized by a browser,
+  // including the properties and styles Elm set on it.
+  serializedAttributes() {
+    const attributes = Object.fromEntries(this.#attributes);
+    for (const [key, value] of Object.entries(this)) {
+      // Elm stores its own bookkeeping (e.g. the event handlers) in `elm` prefixed properties
+      if (key.startsWith("elm") || value === undefined || value === null || value === false || value === "") {
+        continue;
+      }
+      if (typeof value === "string" || typeof value === "number" || value === true) {
+        attributes[PROPERTY_ATTRIBUTES[key] ?? key.toLowerCase()] = value === true ? "" : String(value);
+      }
+    }
+    if (typeof this.#href === "string" && this.#href !== "") {
+      attributes.href = this.#href;
+    }
+    const style = Object.entries(this.#style)
+      .filter(([, value]) => value !== undefined && value !== "")
+      .map(([name, value]) => `${name}: ${value}`)
+      .join("; ");
+    if (style !== "") {
+      attributes.style = style;
+    }
+    return attributes;
+  }
+}
+
+const document = {
+  // The element `Browser.application` programs render their body into, see `init` of the program
+  body: null,
+  title: "",
+  get location() {
+    return location;
+  },
+  createElement: (tag) => new Element(tag),
+  createElementNS: (namespace, tag) => new Element(tag, namespace),
+  createTextNode: (text) => new Text(text),
+  createDocumentFragment: () => new DocumentFragment(),
+  addEventListener() {},
+  removeEventListener() {},
+};
+
+// Frames are drawn right away (as zero delay timers), so the DOM is up to date after every interaction
+const requestAnimationFrame = (callback) => setTimeout(() => callback(Date.now()), 0);
+
+// An element to pass as `node` to `init`, which `Browser.element` programs replace with their view.
+// Its parent becomes the body of the document, which `Browser.application` programs render into.
+export function createRoot() {
+  const container = new Element("body");
+  container.appendChild(new Element("div"));
+  document.body = container;
+  return container;
+}
+
+// The node as a tree of plain objects, e.g. `{ element: { tag: "p", attributes: {}, children: [{ text: "Hi" }] } }`.
+export function toTree(node) {
+  if (node.nodeType === 3) {
+    return { text: node.data };
+  }
+  return {
+    element: {
+      tag: node.localName,
+      attributes: node.serializedAttributes(),
+      children: node.childNodes.map(toTree),
+    },
+  };
+}
+
+// Dispatches an event of the given type to the element at the path (of child indices) below the node,
+// which bubbles up through its ancestors like the events of a browser.
+// The properties (e.g. the `value` of an input) are set on the element first, as typing into it would.
+export function dispatchEvent(node, path, type, properties) {
+  const target = path.reduce((parent, index) => parent.childNodes[index], node);
+  Object.assign(target, properties);
+  let propagationStopped = false;
+  const event = {
+    type,
+    target,
+    currentTarget: target,
+    bubbles: true,
+    defaultPrevented: false,
+    // A plain click with the primary button, which `Browser.application` handles for links
+    button: 0,
+    altKey: false,
+    ctrlKey: false,
+    metaKey: false,
+    shiftKey: false,
+    stopPropagation() {
+      propagationStopped = true;
+    },
+    preventDefault() {
+      this.defaultPrevented = true;
+    },
+  };
+  for (let element = target; element instanceof Element && !propagationStopped; element = element.parentNode) {
+    event.currentTarget = element;
+    element.callListeners(event);
+  }
+}
@@ -0,0 +1,127 @@
+// Http requests of the compiled Elm code below are not sent over the network,
+// but collected until Rust answers them (e.g. with an in-process backend).
+export class PendingRequests {
+  constructor() {
+    this.sent = new Map();
+    this.unanswered = [];
+    this.sequence = 0;
+  }
+
+  send(xhr, body) {
+    const id = ++this.sequence;
+    this.sent.set(id, xhr);
+    this.unanswered.push({
+      id,
+      method: xhr.method,
+      url: xhr.url,
+      headers: xhr.headers,
+      body: requestBody(body),
+      responseType: xhr.responseType,
+    });
+    return id;
+  }
+
+  abort(id) {
+    this.sent.delete(id);
+    this.unanswered = this.unanswered.filter((request) => request.id !== id);
+  }
+
+  hasUnanswered() {
+    return this.unanswered.length > 0;
+  }
+
+  // The requests sent since the last call, which Rust has to answer via `respond`.
+  take() {
+    const requests = this.unanswered;
+    this.unanswered = [];
+    return requests;
+  }
+
+  respond({ id, status, statusText, url, headers, body }) {
+    const xhr = this.sent.get(id);
+    if (xhr === undefined) {
+      // The request was aborted in the meantime
+      return;
+    }
+    this.sent.delete(id);
+    xhr.status = status;
+    xhr.statusText = statusText;
+    xhr.responseURL = url;
+    xhr.responseHeaders = headers;
+    xhr.response = xhr.responseType === "arraybuffer" ? new Uint8Array(body).buffer : body;
+    xhr.readyState = 4;
+    xhr.dispatch("load");
+  }
+}
+
+let pendingRequests;
+
+// Sets the requests the Elm code adds to until the next call.
+export function useHttp(requests) {
+  pendingRequests = requests;
+}
+
+// Strings are sent as is, binary bodies (`Http.bytesBody`) as a list of bytes.
+function requestBody(body) {
+  if (body === null || body === undefined || typeof body === "string") {
+    return body ?? null;
+  }
+  const parts = body instanceof Blob ? body.parts : [body];
+  return parts.flatMap((part) =>
+    Array.from(
+      ArrayBuffer.isView(part)
+        ? new Uint8Array(part.buffer, part.byteOffset, part.byteLength)
+        : new Uint8Array(part)
+    )
+  );
+}
+
+class Blob {
+  constructor(parts = [], options = {}) {
+    this.parts = parts;
+    this.type = options.type ?? "";
+  }
+}
+
+class XMLHttpRequest {
+  constructor() {
+    this.listeners = {};
+    this.headers = [];
+    this.readyState = 0;
+    this.responseType = "";
+    this.upload = { addEventListener() {} };
+  }
+
+  addEventListener(type, listener) {
+    (this.listeners[type] ??= []).push(listener);
+  }
+
+  dispatch(type) {
+    for (const listener of this.listeners[type] ?? []) {
+      listener({ type });
+    }
+  }
+
+  open(method, url) {
+    this.method = method;
+    this.url = url;
+    this.readyState = 1;
+  }
+
+  setRequestHeader(name, value) {
+    this.headers.push([name, value]);
+  }
+
+  send(body) {
+    this.requests = pendingRequests;
+    this.id = pendingRequests.send(this, body);
+  }
+
+  abort() {
+    this.requests?.abort(this.id);
+  }
+
+  getAllResponseHeaders() {
+    return this.responseHeaders ?? "";
+  }
+}
@@ -0,0 +1,152 @@
+// The location and session history of a `Browser.application`, which the compiled Elm code below reads and changes
+// via `document.location`, `history` and `window.location` instead of the ones of a browser.
+export class Navigation {
+  #entries;
+  #index = 0;
+  #listeners = [];
+  #navigations = [];
+
+  constructor(url) {
+    this.#entries = [url];
+  }
+
+  get href() {
+    return this.#entries[this.#index];
+  }
+
+  // The navigations the Elm code requested (e.g. via `Browser.Navigation.pushUrl`) since the last call.
+  take() {
+    const navigations = this.#navigations;
+    this.#navigations = [];
+    return navigations;
+  }
+
+  pushState(url) {
+    this.#push(url);
+    this.#navigations.push({ kind: "pushUrl", url: this.href });
+  }
+
+  replaceState(url) {
+    this.#entries[this.#index] = resolveUrl(String(url), this.href);
+    this.#navigations.push({ kind: "replaceUrl", url: this.href });
+  }
+
+  go(steps) {
+    this.#index = Math.min(Math.max(this.#index + steps, 0), this.#entries.length - 1);
+    this.#navigations.push({ kind: "go", steps });
+  }
+
+  // Leaving the application is only recorded, the location stays the same.
+  load(url) {
+    this.#navigations.push({ kind: "load", url: resolveUrl(String(url), this.href) });
+  }
+
+  reload() {
+    this.#navigations.push({ kind: "reload" });
+  }
+
+  addListener(listener) {
+    this.#listeners.push(listener);
+  }
+
+  removeListener(listener) {
+    this.#listeners = this.#listeners.filter((other) => other !== listener);
+  }
+
+  // Changes the url without the Elm code asking for it (e.g. by following a link outside of the application),
+  // which is passed to its `onUrlChange`.
+  change(url) {
+    this.#push(url);
+    this.#popState();
+  }
+
+  // Goes back in the session history like the back button of a browser, which is passed to `onUrlChange`.
+  back() {
+    if (this.#index > 0) {
+      this.#index--;
+      this.#popState();
+    }
+  }
+
+  #push(url) {
+    this.#entries.splice(this.#index + 1, Infinity, resolveUrl(String(url), this.href));
+    this.#index++;
+  }
+
+  #popState() {
+    for (const listener of [...this.#listeners]) {
+      listener({ type: "popstate", state: null });
+    }
+  }
+}
+
+let navigation;
+
+// Lets the location and history of the Elm code belong to the given navigation (or to none for other programs).
+export function useNavigation(nextNavigation) {
+  navigation = nextNavigation;
+}
+
+// The url relative urls (e.g. the `href` of links) are resolved against.
+function currentUrl() {
+  return navigation?.href ?? "http://localhost/";
+}
+
+// Resolves the url against the absolute base url, without normalizing `.` and `..` segments.
+function resolveUrl(url, base) {
+  if (/^[a-z][a-z0-9+.-]*:/i.test(url)) {
+    return url;
+  }
+  const [, scheme, origin, path] = base.match(/^([a-z][a-z0-9+.-]*:)(\/\/[^/?#]*)?([^?#]*)/i);
+  const withoutFragment = base.replace(/#.*$/, "");
+  if (url.startsWith("//")) {
+    return scheme + url;
+  }
+  if (url.startsWith("/")) {
+    return scheme + (origin ?? "") + url;
+  }
+  if (url.startsWith("?")) {
+    return scheme + (origin ?? "") + path + url;
+  }
+  if (url.startsWith("#") || url === "") {
+    return withoutFragment + url;
+  }
+  return scheme + (origin ?? "") + path.slice(0, path.lastIndexOf("/") + 1) + url;
+}
+
+const location = {
+  get href() {
+    return currentUrl();
+  },
+  reload() {
+    navigation.reload();
+  },
+};
+
+const history = {
+  pushState: (_state, _title, url) => navigation.pushState(url),
+  replaceState: (_state, _title, url) => navigation.replaceState(url),
+  go: (steps) => navigation.go(steps),
+  back: () => navigation.go(-1),
+  forward: () => navigation.go(1),
+};
+
+const window = {
+  navigator: { userAgent: "" },
+  get location() {
+    return location;
+  },
+  set location(url) {
+    navigation.load(url);
+  },
+  addEventListener(type, listener) {
+    if (type === "popstate") {
+      navigation?.addListener(listener);
+    }
+  },
+  removeEventListener(type, listener) {
+    if (type === "popstate") {
+      navigation?.removeListener(listener);
+    }
+  },
+};
@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::Result, json::FallibleOutput, ElmDependencies, ElmFunctionHandle, Error, PortType,
+};
+
+/// The error type of url parser bindings: `True` if the url could not be parsed by `Url.fromString`,
+/// `False` if the route parser returned `Nothing`.
+pub(crate) const URL_ERROR_TYPE: &str = "Bool";
+
+/// A handle to a route parser (a function `Url.Url -> Maybe route`), created via `ElmRoot::prepare_url_parser`.
+pub struct ElmUrlParserHandle<O> {
+    pub(crate) handle: ElmFunctionHandle<String, FallibleOutput>,
+    pub(crate) _type: PhantomData<O>,
+}
+
+impl<O> ElmUrlParserHandle<O>
+where
+    O: DeserializeOwned,
+{
+    /// Builds an `Url.Url` from the given url and parses it with the Elm route parser.
+    /// Path-only urls like `/users/1?tab=posts` are resolved against `http://localhost` first,
+    /// since `Url.fromString` only accepts absolute urls.
+    /// Fails with `Error::InvalidUrl` if `Url.fromString` cannot parse the url.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, url: &str) -> Result<Option<O>> {
+        let result = self.handle.call(absolute_url(url))?;
+        url_parser_output(url, result)
+    }
+
+    /// Builds an `Url.Url` from the given url and parses it with the Elm route parser.
+    /// Path-only urls like `/users/1?tab=posts` are resolved against `http://localhost` first,
+    /// since `Url.fromString` only accepts absolute urls.
+    /// Fails with `Error::InvalidUrl` if `Url.fromString` cannot parse the url.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, url: &str) -> Result<Option<O>> {
+        let result = self.handle.call(absolute_url(url)).await?;
+        url_parser_output(url, result)
+    }
+}
+
+/// Resolves a path-only url (starting with a single `/`) against `http://localhost`.
+fn absolute_url(url: &str) -> String {
+    if url.starts_with('/') && !url.starts_with("//") {
+        format!("{LOCAL_ORIGIN}{url}")
+    } else {
+        url.to_owned()
+    }
+}
+
+const LOCAL_ORIGIN: &str = "http://localhost";
+
+fn url_parser_output<O: DeserializeOwned>(url: &str, output: FallibleOutput) -> Result<Option<O>> {
+    match output.into_result()? {
+        Ok(value) => Ok(Some(serde_json::from_value(value)?)),
+        Err(true) => Err(Box::new(Error::InvalidUrl(url.to_owned()))),
+        Err(false) => Ok(None),
+    }
+}
+
+/// Wraps a route parser into a function building the `Url.Url` from a string first.
+pub(crate) fn parse_url(route_parser: &str) -> String {
+    format!(
+        "Url.fromString >> Result.fromMaybe True >> Result.andThen ({route_parser} >> Result.fromMaybe False)"
+    )
+}
+
+/// A single `String` argument containing the url.
+pub(crate) fn url_input(dependencies: &mut ElmDependencies) -> Result<Vec<PortType>> {
+    dependencies.import("Url");
+    Ok(vec![PortType::Native("String".to_owned())])
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{absolute_url, url_parser_output};
+    use crate::{json::FallibleOutput, Error};
+
+    #[test]
+    fn invalid_url_differs_from_no_route() {
+        let error =
+            url_parser_output::<u32>("not a url", FallibleOutput::failed(json!(true))).unwrap_err();
+        assert!(matches!(*error, Error::InvalidUrl(url) if url == "not a url"));
+        let result =
+            url_parser_output::<u32>("https://example.com", FallibleOutput::failed(json!(false)));
+        assert_eq!(result.unwrap(), None);
+        let result =
+            url_parser_output::<u32>("https://example.com", FallibleOutput::succeeded(json!(1)));
+        assert_eq!(result.unwrap(), Some(1));
+    }
+
+    #[test]
+    fn path_only_urls() {
+        assert_eq!(
+            absolute_url("/users/1?tab=posts"),
+            "http://localhost/users/1?tab=posts"
+        );
+        assert_eq!(absolute_url("https://example.com/"), "https://example.com/");
+        assert_eq!(absolute_url("//example.com/"), "//example.com/");
+    }
+}
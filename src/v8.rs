@@ -1,12 +1,19 @@
 use std::cell::RefCell;
+use std::future::Future;
 use std::marker::PhantomData;
+use std::sync::{Arc, OnceLock};
 
 use rustyscript::Module;
 use rustyscript::ModuleHandle;
 use rustyscript::Runtime;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
+use tokio::runtime::{Handle, RuntimeFlavor};
 
+use crate::clock::ClockSettings;
+use crate::http::{self, CallStep, HttpService};
+use crate::with_browser_shims;
 use crate::ElmBinding;
 use crate::ElmRoot;
 use crate::Error;
@@ -28,20 +35,9 @@ where
         compiled_binding,
         binding_module_name,
     } = elm_binding;
-    // 3. Make the compiled JS esm compatible
-    let to_esm = Module::new("to-esm.js", TO_ESM_JS);
-    let esm_compiled_binding: String = RUNTIME.with_borrow_mut(|runtime| {
-        let handle = runtime.load_module(&to_esm)?;
-        let result: String = runtime.call_entrypoint(&handle, &[compiled_binding])?;
-        Ok::<_, Box<Error>>(result)
-    })?;
-    elm_root.write_esm_binding(&binding_module_name, &esm_compiled_binding)?;
+    let esm_compiled_binding = to_esm(elm_root, &binding_module_name, compiled_binding)?;
     // 4. Load the esm into rustyscript/deno
-    let debug_extras = if elm_root.debug {
-        "console.log('Calling elm binding with', flags);"
-    } else {
-        ""
-    };
+    let debug_extras = debug_extras(elm_root);
     let wrapper = Module::new(
         "run.js",
         RUN_JS_TEMPLATE
@@ -54,14 +50,69 @@ where
 
     Ok(ElmFunctionHandle {
         module: module_handle,
+        clock: elm_root.clock,
+        http: elm_root.http.clone(),
+        tokio: elm_root.tokio.clone(),
         _type: Default::default(),
     })
 }
 
+/// Loads a compiled `Platform.worker` wrapping an Elm program, see `ProgramModule`.
+pub fn prepare_program(elm_root: &ElmRoot, elm_binding: ElmBinding) -> Result<ProgramModule> {
+    let ElmBinding {
+        compiled_binding,
+        binding_module_name,
+    } = elm_binding;
+    let esm_compiled_binding = to_esm(elm_root, &binding_module_name, compiled_binding)?;
+    let wrapper = Module::new(
+        format!("program_{binding_module_name}.js"),
+        PROGRAM_JS_TEMPLATE
+            .replace("{{ binding_module_name }}", &binding_module_name)
+            .replace("{{ debug_extras }}", debug_extras(elm_root)),
+    );
+    let binding_module = Module::new(format!("./{binding_module_name}.js"), &esm_compiled_binding);
+    let module =
+        RUNTIME.with_borrow_mut(|runtime| runtime.load_modules(&wrapper, vec![&binding_module]))?;
+    Ok(ProgramModule {
+        module,
+        clock: elm_root.clock,
+        http: elm_root.http.clone(),
+        tokio: elm_root.tokio.clone(),
+    })
+}
+
+/// 3. Make the compiled JS esm compatible
+fn to_esm(
+    elm_root: &ElmRoot,
+    binding_module_name: &str,
+    compiled_binding: String,
+) -> Result<String> {
+    let to_esm = Module::new("to-esm.js", TO_ESM_JS);
+    let esm_compiled_binding: String = RUNTIME.with_borrow_mut(|runtime| {
+        let handle = runtime.load_module(&to_esm)?;
+        let result: String = runtime.call_entrypoint(&handle, &[compiled_binding])?;
+        Ok::<_, Box<Error>>(result)
+    })?;
+    let esm_compiled_binding = with_browser_shims(&esm_compiled_binding);
+    elm_root.write_esm_binding(binding_module_name, &esm_compiled_binding)?;
+    Ok(esm_compiled_binding)
+}
+
+fn debug_extras(elm_root: &ElmRoot) -> &'static str {
+    if elm_root.debug {
+        "console.log('Calling elm binding with', flags);"
+    } else {
+        ""
+    }
+}
+
 /// A handle to an Elm function. The only thing you can do with this is `call` it.
 /// The main reason this is here, is to only do the `prepare` step once.
 pub struct ElmFunctionHandle<I, O> {
     module: ModuleHandle,
+    clock: ClockSettings,
+    http: Option<HttpService>,
+    tokio: TokioRuntime,
     _type: PhantomData<(I, O)>,
 }
 
@@ -72,10 +123,136 @@ where
 {
     /// Calls the elm function with the given input and return the output.
     pub fn call(&self, input: I) -> Result<O> {
-        let output =
-            RUNTIME.with_borrow_mut(|runtime| runtime.call_entrypoint(&self.module, &[input]))?;
+        let args = [
+            serde_json::to_value(input)?,
+            serde_json::to_value(self.clock)?,
+        ];
+        let mut step: CallStep =
+            RUNTIME.with_borrow_mut(|runtime| runtime.call_entrypoint(&self.module, &args))?;
+        // The call is continued with the answers to its Http requests until it produced its output
+        loop {
+            match step {
+                CallStep::Done { output } => return Ok(serde_json::from_value(output)?),
+                CallStep::Pending { pending, requests } => {
+                    let responses = match http::serve(self.http.as_ref(), &self.tokio, requests) {
+                        Ok(responses) => responses,
+                        Err(error) => {
+                            // The call can not be continued, so the JS side forgets about it
+                            RUNTIME.with_borrow_mut(|runtime| {
+                                runtime.call_function::<()>(
+                                    Some(&self.module),
+                                    "cancel",
+                                    &[pending],
+                                )
+                            })?;
+                            return Err(error);
+                        }
+                    };
+                    let args = [pending.into(), serde_json::to_value(responses)?];
+                    step = RUNTIME.with_borrow_mut(|runtime| {
+                        runtime.call_function(Some(&self.module), "respond", &args)
+                    })?;
+                }
+            }
+        }
+    }
+}
+
+/// The JS module driving the running instances of an Elm program.
+/// Its functions take the id of the instance returned by `init` as their first argument.
+pub struct ProgramModule {
+    module: ModuleHandle,
+    clock: ClockSettings,
+    http: Option<HttpService>,
+    tokio: TokioRuntime,
+}
+
+impl ProgramModule {
+    /// Starts a new instance of the program with its own virtual clock and returns its id.
+    pub fn init(&self, flags: Value) -> Result<usize> {
+        self.start(flags, Value::Null)
+    }
+
+    /// Starts a new instance of a `Browser.application` at the given url and returns its id.
+    pub fn init_application(&self, flags: Value, url: &str) -> Result<usize> {
+        self.start(flags, url.into())
+    }
+
+    fn start(&self, flags: Value, url: Value) -> Result<usize> {
+        let id = self.call("init", vec![flags, serde_json::to_value(self.clock)?, url])?;
+        self.serve_http(id)?;
+        Ok(id)
+    }
+
+    /// Answers the Http requests of the instance, until it does not send any more.
+    pub fn serve_http(&self, id: usize) -> Result<()> {
+        loop {
+            let requests: Vec<_> = self.call("takeHttpRequests", vec![id.into()])?;
+            if requests.is_empty() {
+                return Ok(());
+            }
+            let responses = http::serve(self.http.as_ref(), &self.tokio, requests)?;
+            self.call::<()>(
+                "respondHttp",
+                vec![id.into(), serde_json::to_value(responses)?],
+            )?;
+        }
+    }
+
+    /// The runtime the futures of the Rust side (e.g. of the Http service) run on.
+    pub(crate) fn tokio(&self) -> &TokioRuntime {
+        &self.tokio
+    }
+
+    /// Calls one of the exported functions of the program module.
+    pub fn call<O>(&self, function: &str, args: Vec<Value>) -> Result<O>
+    where
+        O: DeserializeOwned,
+    {
+        let output = RUNTIME.with_borrow_mut(|runtime| {
+            runtime.call_function(Some(&self.module), function, &args)
+        })?;
         Ok(output)
     }
 }
 
+/// The tokio runtime the futures of the Rust side (e.g. of the Http service) run on, since the v8 API is synchronous.
+/// It is shared by everything prepared by the same `ElmRoot` and only created once it is needed,
+/// so tasks spawned by a future keep running between the calls.
+#[derive(Clone, Default)]
+pub(crate) struct TokioRuntime(Arc<OnceLock<tokio::runtime::Runtime>>);
+
+impl TokioRuntime {
+    /// Runs the future to completion. Within a multi threaded tokio runtime (e.g. `#[tokio::test(flavor = "multi_thread")]`)
+    /// the future runs on that runtime, since a runtime can not be started from within another one.
+    pub fn block_on<F>(&self, future: F) -> Result<F::Output>
+    where
+        F: Future,
+    {
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+            }
+            Ok(_) => Err(Box::new(Error::AsyncRuntimeError(
+                "Blocking on a future is only possible within a multi threaded tokio runtime, \
+                 e.g. #[tokio::test(flavor = \"multi_thread\")]"
+                    .to_owned(),
+            ))),
+            Err(_) => Ok(self.runtime()?.block_on(future)),
+        }
+    }
+
+    fn runtime(&self) -> Result<&tokio::runtime::Runtime> {
+        if let Some(runtime) = self.0.get() {
+            return Ok(runtime);
+        }
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|error| Box::new(Error::AsyncRuntimeError(error.to_string())))?;
+        Ok(self.0.get_or_init(|| runtime))
+    }
+}
+
 const RUN_JS_TEMPLATE: &str = include_str!("./templates/run.js.template");
+const PROGRAM_JS_TEMPLATE: &str = include_str!("./templates/program.js.template");
@@ -0,0 +1,177 @@
+use std::{collections::BTreeMap, fmt::Display, marker::PhantomData};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "quickjs")]
+use crate::quickjs::ProgramModule;
+#[cfg(feature = "v8")]
+use crate::v8::ProgramModule;
+use crate::{error::Result, program::decode_flags, PortType};
+
+/// A node of the Html rendered by an Elm view.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HtmlNode {
+    Element(HtmlElement),
+    Text(String),
+}
+
+/// An element of the Html rendered by an Elm view.
+///
+/// Properties set by Elm (e.g. via `Html.Attributes.class`) are included as their attributes (`class`),
+/// boolean properties which are `True` as empty attributes and styles as a single `style` attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HtmlElement {
+    pub tag: String,
+    pub attributes: BTreeMap<String, String>,
+    pub children: Vec<HtmlNode>,
+}
+
+/// Elements which have no closing tag.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Renders the node as normalized Html: attributes are sorted by name and there is no whitespace between tags.
+impl Display for HtmlNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HtmlNode::Element(element) => element.fmt(f),
+            HtmlNode::Text(text) => f.write_str(&escape(text, false)),
+        }
+    }
+}
+
+impl Display for HtmlElement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{}", self.tag)?;
+        for (name, value) in &self.attributes {
+            if value.is_empty() {
+                write!(f, " {name}")?;
+            } else {
+                write!(f, " {name}=\"{}\"", escape(value, true))?;
+            }
+        }
+        f.write_str(">")?;
+        if VOID_ELEMENTS.contains(&self.tag.as_str()) {
+            return Ok(());
+        }
+        for child in &self.children {
+            child.fmt(f)?;
+        }
+        write!(f, "</{}>", self.tag)
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+/// A handle to an Elm view function (`input -> Html msg`), created via `ElmRoot::prepare_view`.
+pub struct ElmViewHandle<I> {
+    pub(crate) module: ProgramModule,
+    pub(crate) _type: PhantomData<I>,
+}
+
+impl<I> ElmViewHandle<I>
+where
+    I: Serialize,
+{
+    /// Renders the view for the given input. Use `to_string` on the result for the normalized Html.
+    #[cfg(feature = "v8")]
+    pub fn call(&self, input: I) -> Result<HtmlNode> {
+        let id = self.module.init(serde_json::to_value(input)?)?;
+        // Dispose the program even if rendering failed, so it is not leaked
+        let html = self.module.call("html", vec![id.into()]);
+        self.module.call::<()>("dispose", vec![id.into()])?;
+        html
+    }
+
+    /// Renders the view for the given input. Use `to_string` on the result for the normalized Html.
+    #[cfg(feature = "quickjs")]
+    pub async fn call(&self, input: I) -> Result<HtmlNode> {
+        let id = self.module.init(serde_json::to_value(input)?).await?;
+        // Dispose the program even if rendering failed, so it is not leaked
+        let html = self.module.call("html", vec![id.into()]).await;
+        self.module.call::<()>("dispose", vec![id.into()]).await?;
+        html
+    }
+}
+
+/// Generates a `Browser.element` rendering the given view function with the flags as its input.
+pub(crate) fn view_elm(
+    file_name: &str,
+    view: &str,
+    imports: &str,
+    declarations: &str,
+    input: &PortType,
+) -> String {
+    VIEW_TEMPLATE
+        .replace("{{ file_name }}", file_name)
+        .replace("{{ imports }}", imports)
+        .replace("{{ declarations }}", declarations)
+        .replace("{{ view }}", view)
+        .replace("{{ decode_flags }}", &decode_flags(input))
+}
+
+/// Prepends the DOM shim to the ESM compatible binding.
+pub(crate) fn with_dom_shim(esm_compiled_binding: &str) -> String {
+    format!("{DOM_JS}{esm_compiled_binding}")
+}
+
+const VIEW_TEMPLATE: &str = include_str!("./templates/View.elm.template");
+const DOM_JS: &str = include_str!("./templates/dom.js");
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_json::json;
+
+    use super::{HtmlElement, HtmlNode};
+
+    #[test]
+    fn normalized_html() {
+        let html: HtmlNode = serde_json::from_value(json!({
+            "element": {
+                "tag": "form",
+                "attributes": { "id": "login", "class": "card \"wide\"" },
+                "children": [
+                    { "element": { "tag": "input", "attributes": { "disabled": "" }, "children": [] } },
+                    { "text": "Log in & continue <3" }
+                ]
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            html.to_string(),
+            r#"<form class="card &quot;wide&quot;" id="login"><input disabled>Log in &amp; continue &lt;3</form>"#
+        );
+    }
+
+    #[test]
+    fn tree_roundtrip() {
+        let html = HtmlNode::Element(HtmlElement {
+            tag: "p".to_owned(),
+            attributes: BTreeMap::from([("class".to_owned(), "greeting".to_owned())]),
+            children: vec![HtmlNode::Text("Hello".to_owned())],
+        });
+        let json = serde_json::to_value(&html).unwrap();
+        assert_eq!(
+            json,
+            json!({ "element": { "tag": "p", "attributes": { "class": "greeting" }, "children": [{ "text": "Hello" }] } })
+        );
+        assert_eq!(serde_json::from_value::<HtmlNode>(json).unwrap(), html);
+    }
+}
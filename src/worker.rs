@@ -0,0 +1,511 @@
+#[cfg(feature = "quickjs")]
+use std::pin::Pin;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+    rc::Rc,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[cfg(feature = "quickjs")]
+use crate::quickjs::ProgramModule;
+#[cfg(feature = "v8")]
+use crate::v8::ProgramModule;
+use crate::{clock::millis, error::Result, Error, PortMessage};
+
+/// A handle to a compiled `Platform.worker`, created via `ElmRoot::prepare_worker`.
+/// Every call to `init` starts a new, independent instance of the worker.
+pub struct ElmWorkerHandle<Flags> {
+    pub(crate) module: ProgramModule,
+    pub(crate) _type: PhantomData<Flags>,
+}
+
+impl<Flags> ElmWorkerHandle<Flags>
+where
+    Flags: Serialize,
+{
+    /// Starts an instance of the worker with the given flags.
+    /// Messages its `init` sends to outgoing ports are kept until a receiver (or handler) is registered.
+    #[cfg(feature = "v8")]
+    pub fn init(&self, flags: Flags) -> Result<ElmWorker<'_>> {
+        let id: usize = self.module.init(serde_json::to_value(flags)?)?;
+        let ports = self.module.call("ports", vec![id.into()])?;
+        let worker = ElmWorker::new(&self.module, id, ports);
+        worker.process()?;
+        Ok(worker)
+    }
+
+    /// Starts an instance of the worker with the given flags.
+    /// Messages its `init` sends to outgoing ports are kept until a receiver (or handler) is registered.
+    #[cfg(feature = "quickjs")]
+    pub async fn init(&self, flags: Flags) -> Result<ElmWorker<'_>> {
+        let id: usize = self.module.init(serde_json::to_value(flags)?).await?;
+        let ports = self.module.call("ports", vec![id.into()]).await?;
+        let worker = ElmWorker::new(&self.module, id, ports);
+        worker.process().await?;
+        Ok(worker)
+    }
+}
+
+/// The names of the ports of a worker.
+#[derive(Deserialize, Debug, Default)]
+struct Ports {
+    incoming: Vec<String>,
+    outgoing: Vec<String>,
+}
+
+/// A running instance of a `Platform.worker`, which is driven through its ports.
+///
+/// Messages of outgoing ports are delivered to the receivers returned by `receiver`,
+/// or answered by the handlers registered via `handle_port`.
+/// Messages sent before a receiver (or handler) for their port was registered are kept until then.
+///
+/// The instance is removed from the JS runtime when the worker is dropped (with the `v8` feature)
+/// or via `dispose` (with the `quickjs` feature, since dropping can not wait for the JS runtime).
+pub struct ElmWorker<'a> {
+    module: &'a ProgramModule,
+    id: usize,
+    ports: Ports,
+    receivers: RefCell<Receivers>,
+    handlers: RefCell<HashMap<String, PortHandler>>,
+}
+
+/// Answers the messages of an outgoing port by sending the response to an incoming port.
+#[derive(Clone)]
+struct PortHandler {
+    incoming: String,
+    respond: Rc<Respond>,
+}
+
+#[cfg(feature = "v8")]
+type Respond = dyn Fn(Value) -> Result<Value>;
+#[cfg(feature = "quickjs")]
+type Respond = dyn Fn(Value) -> Pin<Box<dyn Future<Output = Result<Value>>>>;
+
+impl<'a> ElmWorker<'a> {
+    fn new(module: &'a ProgramModule, id: usize, ports: Ports) -> Self {
+        Self {
+            module,
+            id,
+            ports,
+            receivers: RefCell::default(),
+            handlers: RefCell::default(),
+        }
+    }
+
+    /// The names of the incoming ports (`port loaded : (a -> msg) -> Sub msg`) of the worker.
+    pub fn incoming_ports(&self) -> &[String] {
+        &self.ports.incoming
+    }
+
+    /// The names of the outgoing ports (`port save : a -> Cmd msg`) of the worker.
+    pub fn outgoing_ports(&self) -> &[String] {
+        &self.ports.outgoing
+    }
+
+    /// A sender for the incoming port with the given name.
+    pub fn sender<T>(&self, port: &str) -> Result<PortSender<'_, 'a, T>>
+    where
+        T: Serialize,
+    {
+        check_port(&self.ports.incoming, port)?;
+        Ok(PortSender {
+            worker: self,
+            port: port.to_owned(),
+            _type: PhantomData,
+        })
+    }
+
+    /// A receiver for the outgoing port with the given name.
+    /// New messages are delivered whenever a value is sent to the worker.
+    pub fn receiver<T>(&self, port: &str) -> Result<Receiver<T>>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        check_port(&self.ports.outgoing, port)?;
+        self.receivers.borrow_mut().register(port)
+    }
+
+    /// Answers every message of the `outgoing` port with the result of the handler, which is sent to the `incoming` port.
+    /// This replaces the JS side of request/response style ports, e.g. `askBackend`/`backendReplied`.
+    #[cfg(feature = "v8")]
+    pub fn handle_port<Req, Resp>(
+        &self,
+        outgoing: &str,
+        incoming: &str,
+        handler: impl Fn(Req) -> Resp + 'static,
+    ) -> Result<()>
+    where
+        Req: DeserializeOwned + 'static,
+        Resp: Serialize + 'static,
+    {
+        let respond: Rc<Respond> = Rc::new(move |request| {
+            Ok(serde_json::to_value(handler(serde_json::from_value(
+                request,
+            )?))?)
+        });
+        let requests = self.register_handler(outgoing, incoming, respond)?;
+        self.respond(requests)
+    }
+
+    /// Answers every message of the `outgoing` port with the result of the handler, which is sent to the `incoming` port.
+    /// This replaces the JS side of request/response style ports, e.g. `askBackend`/`backendReplied`.
+    #[cfg(feature = "quickjs")]
+    pub async fn handle_port<Req, Resp>(
+        &self,
+        outgoing: &str,
+        incoming: &str,
+        handler: impl Fn(Req) -> Resp + 'static,
+    ) -> Result<()>
+    where
+        Req: DeserializeOwned + 'static,
+        Resp: Serialize + 'static,
+    {
+        let handler = Rc::new(handler);
+        self.handle_port_async(outgoing, incoming, move |request| {
+            let handler = handler.clone();
+            async move { handler(request) }
+        })
+        .await
+    }
+
+    /// Like `handle_port`, but with a handler returning a future, e.g. to forward the request to a test server.
+    /// The future runs on the same tokio runtime as the Http service, see `ElmRoot::http_service`.
+    #[cfg(feature = "v8")]
+    pub fn handle_port_async<Req, Resp, Fut>(
+        &self,
+        outgoing: &str,
+        incoming: &str,
+        handler: impl Fn(Req) -> Fut + 'static,
+    ) -> Result<()>
+    where
+        Req: DeserializeOwned + 'static,
+        Resp: Serialize + 'static,
+        Fut: Future<Output = Resp> + 'static,
+    {
+        let tokio = self.module.tokio().clone();
+        let respond: Rc<Respond> = Rc::new(move |request| {
+            let response = tokio.block_on(handler(serde_json::from_value(request)?))?;
+            Ok(serde_json::to_value(response)?)
+        });
+        let requests = self.register_handler(outgoing, incoming, respond)?;
+        self.respond(requests)
+    }
+
+    /// Like `handle_port`, but with a handler returning a future, e.g. to forward the request to a test server.
+    #[cfg(feature = "quickjs")]
+    pub async fn handle_port_async<Req, Resp, Fut>(
+        &self,
+        outgoing: &str,
+        incoming: &str,
+        handler: impl Fn(Req) -> Fut + 'static,
+    ) -> Result<()>
+    where
+        Req: DeserializeOwned + 'static,
+        Resp: Serialize + 'static,
+        Fut: Future<Output = Resp> + 'static,
+    {
+        let handler = Rc::new(handler);
+        let respond: Rc<Respond> = Rc::new(move |request| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                let response = handler(serde_json::from_value(request)?).await;
+                Ok(serde_json::to_value(response)?)
+            })
+        });
+        let requests = self.register_handler(outgoing, incoming, respond)?;
+        self.respond(requests).await
+    }
+
+    /// Registers the handler and returns the pending messages of its port.
+    fn register_handler(
+        &self,
+        outgoing: &str,
+        incoming: &str,
+        respond: Rc<Respond>,
+    ) -> Result<Vec<(PortHandler, Value)>> {
+        check_port(&self.ports.outgoing, outgoing)?;
+        check_port(&self.ports.incoming, incoming)?;
+        let handler = PortHandler {
+            incoming: incoming.to_owned(),
+            respond,
+        };
+        self.handlers
+            .borrow_mut()
+            .insert(outgoing.to_owned(), handler.clone());
+        Ok(self
+            .receivers
+            .borrow_mut()
+            .take_pending(outgoing)
+            .into_iter()
+            .map(|request| (handler.clone(), request))
+            .collect())
+    }
+
+    /// Delivers the messages of the outgoing ports to their receivers and returns the ones which have a handler.
+    fn route(&self, port_messages: Vec<PortMessage>) -> Result<Vec<(PortHandler, Value)>> {
+        let handlers = self.handlers.borrow();
+        let (requests, port_messages): (Vec<_>, Vec<_>) = port_messages
+            .into_iter()
+            .partition(|message| handlers.contains_key(&message.port));
+        self.receivers.borrow_mut().dispatch(port_messages)?;
+        Ok(requests
+            .into_iter()
+            .map(|message| (handlers[&message.port].clone(), message.value))
+            .collect())
+    }
+
+    #[cfg(feature = "v8")]
+    fn respond(&self, requests: Vec<(PortHandler, Value)>) -> Result<()> {
+        let responses = requests
+            .into_iter()
+            .map(|(handler, request)| Ok((handler.incoming, (handler.respond)(request)?)))
+            .collect::<Result<_>>()?;
+        self.send_all(responses)
+    }
+
+    #[cfg(feature = "quickjs")]
+    async fn respond(&self, requests: Vec<(PortHandler, Value)>) -> Result<()> {
+        let mut responses = Vec::new();
+        for (handler, request) in requests {
+            responses.push((handler.incoming, (handler.respond)(request).await?));
+        }
+        self.send_all(responses).await
+    }
+
+    /// Removes the instance of the worker from the JS runtime, which keeps it until then.
+    #[cfg(feature = "quickjs")]
+    pub async fn dispose(self) -> Result<()> {
+        self.module
+            .call::<()>("dispose", vec![self.id.into()])
+            .await
+    }
+
+    /// Lets the given amount of time pass on the virtual clock of the worker, running its timers
+    /// (e.g. of `Process.sleep` or `Time.every`) in order.
+    #[cfg(feature = "v8")]
+    pub fn advance(&self, duration: Duration) -> Result<()> {
+        self.module
+            .call::<()>("advance", vec![self.id.into(), millis(duration).into()])?;
+        self.process()
+    }
+
+    /// Lets the given amount of time pass on the virtual clock of the worker, running its timers
+    /// (e.g. of `Process.sleep` or `Time.every`) in order.
+    #[cfg(feature = "quickjs")]
+    pub async fn advance(&self, duration: Duration) -> Result<()> {
+        self.module
+            .call::<()>("advance", vec![self.id.into(), millis(duration).into()])
+            .await?;
+        self.process().await
+    }
+
+    /// Sends the values to their ports and processes the resulting port messages.
+    #[cfg(feature = "v8")]
+    fn send_all(&self, values: Vec<(String, Value)>) -> Result<()> {
+        for (port, value) in values {
+            self.module
+                .call::<()>("sendPort", vec![self.id.into(), port.into(), value])?;
+        }
+        self.process()
+    }
+
+    /// Sends the values to their ports and processes the resulting port messages.
+    #[cfg(feature = "quickjs")]
+    async fn send_all(&self, values: Vec<(String, Value)>) -> Result<()> {
+        for (port, value) in values {
+            self.module
+                .call::<()>("sendPort", vec![self.id.into(), port.into(), value])
+                .await?;
+        }
+        self.process().await
+    }
+
+    /// Answers the Http requests and delivers the port messages of the worker,
+    /// until no more handlers have to respond to them.
+    #[cfg(feature = "v8")]
+    fn process(&self) -> Result<()> {
+        loop {
+            self.module.serve_http(self.id)?;
+            let port_messages = self.module.call("takePortMessages", vec![self.id.into()])?;
+            let requests = self.route(port_messages)?;
+            if requests.is_empty() {
+                return Ok(());
+            }
+            for (handler, request) in requests {
+                let response = (handler.respond)(request)?;
+                self.module.call::<()>(
+                    "sendPort",
+                    vec![self.id.into(), handler.incoming.into(), response],
+                )?;
+            }
+        }
+    }
+
+    /// Answers the Http requests and delivers the port messages of the worker,
+    /// until no more handlers have to respond to them.
+    #[cfg(feature = "quickjs")]
+    async fn process(&self) -> Result<()> {
+        loop {
+            self.module.serve_http(self.id).await?;
+            let port_messages = self
+                .module
+                .call("takePortMessages", vec![self.id.into()])
+                .await?;
+            let requests = self.route(port_messages)?;
+            if requests.is_empty() {
+                return Ok(());
+            }
+            for (handler, request) in requests {
+                let response = (handler.respond)(request).await?;
+                self.module
+                    .call::<()>(
+                        "sendPort",
+                        vec![self.id.into(), handler.incoming.into(), response],
+                    )
+                    .await?;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "v8")]
+impl Drop for ElmWorker<'_> {
+    fn drop(&mut self) {
+        // Errors can not be reported while dropping, and the instance is not used anymore anyway
+        let _ = self.module.call::<()>("dispose", vec![self.id.into()]);
+    }
+}
+
+fn check_port(ports: &[String], port: &str) -> Result<()> {
+    if ports.iter().any(|name| name == port) {
+        Ok(())
+    } else {
+        Err(Box::new(Error::UnknownPort(port.to_owned())))
+    }
+}
+
+type Dispatch = Box<dyn Fn(Value) -> Result<()>>;
+
+/// The receivers of the outgoing ports, along with the messages of ports without a receiver.
+#[derive(Default)]
+struct Receivers {
+    receivers: HashMap<String, Dispatch>,
+    pending: Vec<PortMessage>,
+}
+
+impl Receivers {
+    /// Creates a channel for the given port and delivers the pending messages of the port.
+    fn register<T>(&mut self, port: &str) -> Result<Receiver<T>>
+    where
+        T: DeserializeOwned + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let dispatch: Dispatch = Box::new(move |value| {
+            // The receiver might have been dropped, in which case the messages are no longer of interest
+            let _ = sender.send(serde_json::from_value(value)?);
+            Ok(())
+        });
+        for value in self.take_pending(port) {
+            dispatch(value)?;
+        }
+        self.receivers.insert(port.to_owned(), dispatch);
+        Ok(receiver)
+    }
+
+    /// Removes the pending messages of the given port.
+    fn take_pending(&mut self, port: &str) -> Vec<Value> {
+        let (matching, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|message| message.port == port);
+        self.pending = pending;
+        matching.into_iter().map(|message| message.value).collect()
+    }
+
+    /// Delivers the given port messages to their receivers.
+    fn dispatch(&mut self, port_messages: Vec<PortMessage>) -> Result<()> {
+        for message in port_messages {
+            match self.receivers.get(&message.port) {
+                Some(dispatch) => dispatch(message.value)?,
+                None => self.pending.push(message),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sends values of type `T` to an incoming port of an `ElmWorker`.
+pub struct PortSender<'w, 'a, T> {
+    worker: &'w ElmWorker<'a>,
+    port: String,
+    _type: PhantomData<T>,
+}
+
+impl<T> PortSender<'_, '_, T>
+where
+    T: Serialize,
+{
+    /// Sends the value to the port and waits until the worker processed it.
+    #[cfg(feature = "v8")]
+    pub fn send(&self, value: T) -> Result<()> {
+        self.worker
+            .send_all(vec![(self.port.clone(), serde_json::to_value(value)?)])
+    }
+
+    /// Sends the value to the port and waits until the worker processed it.
+    #[cfg(feature = "quickjs")]
+    pub async fn send(&self, value: T) -> Result<()> {
+        self.worker
+            .send_all(vec![(self.port.clone(), serde_json::to_value(value)?)])
+            .await
+    }
+}
+
+/// A module re-exporting the `main` of the given module, so its ports can be accessed under the binding module name.
+pub(crate) fn worker_elm(file_name: &str, module: &str) -> String {
+    format!(
+        "module {file_name} exposing (main)\n\nimport {module}\n\n\nmain =\n    {module}.main\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{worker_elm, Receivers};
+    use crate::PortMessage;
+
+    fn message(port: &str, value: i32) -> PortMessage {
+        PortMessage {
+            port: port.to_owned(),
+            value: json!(value),
+        }
+    }
+
+    #[test]
+    fn pending_messages() {
+        let mut receivers = Receivers::default();
+        receivers
+            .dispatch(vec![message("save", 1), message("log", 2)])
+            .unwrap();
+        let save = receivers.register::<i32>("save").unwrap();
+        receivers.dispatch(vec![message("save", 3)]).unwrap();
+        assert_eq!(save.try_iter().collect::<Vec<_>>(), [1, 3]);
+        let log = receivers.register::<i32>("log").unwrap();
+        assert_eq!(log.try_iter().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn worker_module() {
+        assert_eq!(
+            worker_elm("Worker_Worker1", "Worker"),
+            "module Worker_Worker1 exposing (main)\n\nimport Worker\n\n\nmain =\n    Worker.main\n"
+        );
+    }
+}
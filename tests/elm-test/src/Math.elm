@@ -0,0 +1,13 @@
+module Math exposing (clampInt, square)
+
+
+square : Int -> Int
+square n =
+    n * n
+
+
+clampInt : Int -> Int -> Int -> Int
+clampInt low high n =
+    -- Deliberately wrong for n > high
+    max low n
+        |> min (high + 1)
@@ -0,0 +1,32 @@
+module MathTest exposing (clampSuite, squareSuite)
+
+import Expect
+import Fuzz
+import Math
+import Test exposing (Test, describe, fuzz, test)
+
+
+squareSuite : Test
+squareSuite =
+    describe "square"
+        [ test "of 3" <|
+            \_ -> Math.square 3 |> Expect.equal 9
+        , fuzz Fuzz.int "is not negative" <|
+            \n -> Math.square n |> Expect.atLeast 0
+        ]
+
+
+clampSuite : Test
+clampSuite =
+    describe "clampInt"
+        [ test "below the range" <|
+            \_ -> Math.clampInt 0 10 -5 |> Expect.equal 0
+        , test "above the range" <|
+            \_ -> Math.clampInt 0 10 15 |> Expect.equal 10
+        ]
+
+
+helperSuite : Test
+helperSuite =
+    test "not exposed" <|
+        \_ -> Expect.fail "should not be run"
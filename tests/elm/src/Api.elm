@@ -0,0 +1,56 @@
+module Api exposing (fetchUserName)
+
+import Http
+import Json.Decode
+import Task exposing (Task)
+
+
+fetchUserName : Int -> Task String String
+fetchUserName id =
+    Http.task
+        { method = "GET"
+        , headers = [ Http.header "Accept" "application/json" ]
+        , url = "/users/" ++ String.fromInt id
+        , body = Http.emptyBody
+        , resolver = Http.stringResolver (decodeName >> Result.mapError describe)
+        , timeout = Nothing
+        }
+
+
+decodeName : Http.Response String -> Result Http.Error String
+decodeName response =
+    case response of
+        Http.GoodStatus_ _ body ->
+            Json.Decode.decodeString (Json.Decode.field "name" Json.Decode.string) body
+                |> Result.mapError (Json.Decode.errorToString >> Http.BadBody)
+
+        Http.BadStatus_ metadata _ ->
+            Err (Http.BadStatus metadata.statusCode)
+
+        Http.BadUrl_ url ->
+            Err (Http.BadUrl url)
+
+        Http.Timeout_ ->
+            Err Http.Timeout
+
+        Http.NetworkError_ ->
+            Err Http.NetworkError
+
+
+describe : Http.Error -> String
+describe error =
+    case error of
+        Http.BadUrl url ->
+            "bad url " ++ url
+
+        Http.Timeout ->
+            "timeout"
+
+        Http.NetworkError ->
+            "network error"
+
+        Http.BadStatus status ->
+            "status " ++ String.fromInt status
+
+        Http.BadBody body ->
+            "bad body " ++ body
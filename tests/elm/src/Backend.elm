@@ -0,0 +1,32 @@
+port module Backend exposing (main)
+
+
+port askBackend : Int -> Cmd msg
+
+
+port backendReplied : (String -> msg) -> Sub msg
+
+
+port done : List String -> Cmd msg
+
+
+main : Program () (List String) String
+main =
+    Platform.worker
+        { init = \_ -> ( [], askBackend 1 )
+        , update = update
+        , subscriptions = \_ -> backendReplied identity
+        }
+
+
+update : String -> List String -> ( List String, Cmd String )
+update reply replies =
+    let
+        allReplies =
+            replies ++ [ reply ]
+    in
+    if List.length allReplies < 3 then
+        ( allReplies, askBackend (List.length allReplies + 1) )
+
+    else
+        ( allReplies, done allReplies )
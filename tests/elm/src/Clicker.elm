@@ -0,0 +1,27 @@
+module Clicker exposing (Model, Msg(..), init, update, view)
+
+import Html exposing (Html)
+import Html.Events
+
+
+type alias Model =
+    Int
+
+
+type Msg
+    = Clicked
+
+
+init : Model
+init =
+    0
+
+
+update : Msg -> Model -> Model
+update Clicked count =
+    count + 1
+
+
+view : Model -> Html Msg
+view count =
+    Html.button [ Html.Events.onClick Clicked ] [ Html.text (String.fromInt count) ]
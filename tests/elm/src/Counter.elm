@@ -0,0 +1,37 @@
+port module Counter exposing (Model, Msg(..), init, subscriptions, update)
+
+
+port saved : Int -> Cmd msg
+
+
+type alias Model =
+    { count : Int, history : List Int }
+
+
+type Msg
+    = Increment
+    | Add Int
+    | Save
+
+
+init : Int -> ( Model, Cmd Msg )
+init start =
+    ( { count = start, history = [] }, Cmd.none )
+
+
+update : Msg -> Model -> ( Model, Cmd Msg )
+update msg model =
+    case msg of
+        Increment ->
+            ( { model | count = model.count + 1 }, Cmd.none )
+
+        Add n ->
+            ( { model | count = model.count + n }, Cmd.none )
+
+        Save ->
+            ( { model | history = model.count :: model.history }, saved model.count )
+
+
+subscriptions : Model -> Sub Msg
+subscriptions _ =
+    Sub.none
@@ -0,0 +1,14 @@
+module Gen exposing (Level, level)
+
+import Random exposing (Generator)
+
+
+type alias Level =
+    { width : Int, height : Int }
+
+
+level : Generator Level
+level =
+    Random.map2 Level
+        (Random.int 1 10)
+        (Random.int 1 10)
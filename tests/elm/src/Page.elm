@@ -0,0 +1,16 @@
+module Page exposing (view)
+
+import Html exposing (Html)
+import Html.Attributes
+
+
+type alias User =
+    { name : String, admin : Bool }
+
+
+view : User -> Html msg
+view user =
+    Html.div [ Html.Attributes.class "user", Html.Attributes.style "color" "red" ]
+        [ Html.h1 [] [ Html.text ("Hello, " ++ user.name) ]
+        , Html.input [ Html.Attributes.disabled (not user.admin), Html.Attributes.value user.name ] []
+        ]
@@ -0,0 +1,11 @@
+module Private exposing (double)
+
+
+double : Int -> Int
+double n =
+    n * 2
+
+
+triple : Int -> Int
+triple n =
+    n * 3
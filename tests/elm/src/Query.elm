@@ -0,0 +1,29 @@
+module Query exposing (parser, quoted)
+
+import Parser exposing ((|.), (|=), Parser)
+import Set
+
+
+type alias Filter =
+    { field : String, value : Int }
+
+
+parser : Parser Filter
+parser =
+    Parser.succeed Filter
+        |= Parser.variable
+            { start = Char.isAlpha
+            , inner = Char.isAlphaNum
+            , reserved = Set.empty
+            }
+        |. Parser.symbol "="
+        |= Parser.int
+        |. Parser.end
+
+
+quoted : Parser String
+quoted =
+    Parser.succeed identity
+        |. Parser.symbol "\""
+        |= Parser.getChompedString (Parser.chompWhile ((/=) '"'))
+        |. Parser.symbol "\""
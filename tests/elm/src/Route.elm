@@ -0,0 +1,22 @@
+module Route exposing (Route(..), fromUrl)
+
+import Url exposing (Url)
+import Url.Parser exposing ((</>), Parser, int, map, oneOf, s, top)
+
+
+type Route
+    = Home
+    | User Int
+
+
+fromUrl : Url -> Maybe Route
+fromUrl =
+    Url.Parser.parse parser
+
+
+parser : Parser (Route -> a) a
+parser =
+    oneOf
+        [ map Home top
+        , map User (s "users" </> int)
+        ]
@@ -0,0 +1,60 @@
+module Shop exposing (Model, Msg, init, onUrlChange, onUrlRequest, subscriptions, update, view)
+
+import Browser
+import Browser.Navigation as Nav
+import Html
+import Html.Attributes
+import Url exposing (Url)
+
+
+type alias Model =
+    { key : Nav.Key, path : String }
+
+
+type Msg
+    = UrlRequested Browser.UrlRequest
+    | UrlChanged Url
+
+
+init : () -> Url -> Nav.Key -> ( Model, Cmd Msg )
+init _ url key =
+    ( { key = key, path = url.path }, Cmd.none )
+
+
+update : Msg -> Model -> ( Model, Cmd Msg )
+update msg model =
+    case msg of
+        UrlRequested (Browser.Internal url) ->
+            ( model, Nav.pushUrl model.key (Url.toString url) )
+
+        UrlRequested (Browser.External href) ->
+            ( model, Nav.load href )
+
+        UrlChanged url ->
+            ( { model | path = url.path }, Cmd.none )
+
+
+subscriptions : Model -> Sub Msg
+subscriptions _ =
+    Sub.none
+
+
+onUrlRequest : Browser.UrlRequest -> Msg
+onUrlRequest =
+    UrlRequested
+
+
+onUrlChange : Url -> Msg
+onUrlChange =
+    UrlChanged
+
+
+view : Model -> Browser.Document Msg
+view model =
+    { title = "Shop"
+    , body =
+        [ Html.h1 [] [ Html.text model.path ]
+        , Html.a [ Html.Attributes.class "product", Html.Attributes.href "/products/7" ] [ Html.text "Product" ]
+        , Html.a [ Html.Attributes.class "help", Html.Attributes.href "https://example.org/help" ] [ Html.text "Help" ]
+        ]
+    }
@@ -0,0 +1,50 @@
+module Signup exposing (Model, Msg(..), init, subscriptions, update, view)
+
+import Html exposing (Html)
+import Html.Attributes
+import Html.Events
+
+
+type alias Model =
+    { name : String, signedUp : List String }
+
+
+type Msg
+    = NameChanged String
+    | Submitted
+    | Cleared
+
+
+init : String -> ( Model, Cmd Msg )
+init name =
+    ( { name = name, signedUp = [] }, Cmd.none )
+
+
+update : Msg -> Model -> ( Model, Cmd Msg )
+update msg model =
+    case msg of
+        NameChanged name ->
+            ( { model | name = name }, Cmd.none )
+
+        Submitted ->
+            ( { name = "", signedUp = model.signedUp ++ [ model.name ] }, Cmd.none )
+
+        Cleared ->
+            ( { model | signedUp = [] }, Cmd.none )
+
+
+subscriptions : Model -> Sub Msg
+subscriptions _ =
+    Sub.none
+
+
+view : Model -> Html Msg
+view model =
+    Html.div []
+        [ Html.form [ Html.Events.onSubmit Submitted ]
+            [ Html.input [ Html.Attributes.name "name", Html.Attributes.value model.name, Html.Events.onInput NameChanged ] []
+            , Html.button [ Html.Attributes.type_ "submit" ] [ Html.text "Sign up" ]
+            ]
+        , Html.ul [] (List.map (\name -> Html.li [] [ Html.text name ]) model.signedUp)
+        , Html.button [ Html.Attributes.class "clear", Html.Events.onClick Cleared ] [ Html.text "Clear" ]
+        ]
@@ -0,0 +1,36 @@
+module Tasks exposing (delayedDouble, divide, positive, sleepingNow)
+
+import Process
+import Task exposing (Task)
+import Time
+
+
+delayedDouble : Int -> Task String Int
+delayedDouble n =
+    Process.sleep 1000
+        |> Task.map (\_ -> n * 2)
+
+
+divide : ( Int, Int ) -> Task String Int
+divide ( dividend, divisor ) =
+    if divisor == 0 then
+        Task.fail "division by zero"
+
+    else
+        Task.succeed (dividend // divisor)
+
+
+positive : Int -> Task () Int
+positive n =
+    if n > 0 then
+        Task.succeed n
+
+    else
+        Task.fail ()
+
+
+sleepingNow : Float -> Task String Int
+sleepingNow duration =
+    Process.sleep duration
+        |> Task.andThen (\_ -> Time.now)
+        |> Task.map Time.posixToMillis
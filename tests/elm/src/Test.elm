@@ -1,5 +1,8 @@
 module Test exposing (..)
 
+import Json.Decode
+import Json.Encode
+
 
 add5 : Int -> Int
 add5 =
@@ -34,3 +37,60 @@ someStructMapper =
             }
     in
     List.map mapOne
+
+
+type Shape
+    = Circle Float
+    | Rectangle { width : Float, height : Float }
+    | Empty
+
+
+scale : Shape -> Shape
+scale shape =
+    case shape of
+        Circle radius ->
+            Circle (radius * 2)
+
+        Rectangle { width, height } ->
+            Rectangle { width = width * 2, height = height * 2 }
+
+        Empty ->
+            Empty
+
+
+repeat : Int -> String -> String
+repeat =
+    String.repeat
+
+
+clampInt : Int -> Int -> Int -> Int
+clampInt =
+    clamp
+
+
+defaultStruct : StructOut
+defaultStruct =
+    { c = [ 1, 2, 3 ], d = Nothing }
+
+
+structOutDecoder : Json.Decode.Decoder StructOut
+structOutDecoder =
+    Json.Decode.map2 StructOut
+        (Json.Decode.field "numbers" (Json.Decode.list Json.Decode.int))
+        (Json.Decode.maybe (Json.Decode.field "flag" Json.Decode.bool))
+
+
+encodeStructIn : StructIn -> Json.Encode.Value
+encodeStructIn { a, b } =
+    Json.Encode.object
+        [ ( "maybeNumber", a |> Maybe.map Json.Encode.int |> Maybe.withDefault Json.Encode.null )
+        , ( "flags", Json.Encode.list Json.Encode.bool b )
+        ]
+
+
+encodeStructOut : StructOut -> Json.Encode.Value
+encodeStructOut { c, d } =
+    Json.Encode.object
+        (( "numbers", Json.Encode.list Json.Encode.int c )
+            :: (d |> Maybe.map (\flag -> [ ( "flag", Json.Encode.bool flag ) ]) |> Maybe.withDefault [])
+        )
@@ -0,0 +1,15 @@
+port module Ticker exposing (main)
+
+import Time
+
+
+port ticked : Int -> Cmd msg
+
+
+main : Program () () Time.Posix
+main =
+    Platform.worker
+        { init = \_ -> ( (), Cmd.none )
+        , update = \time model -> ( model, ticked (Time.posixToMillis time) )
+        , subscriptions = \_ -> Time.every 1000 identity
+        }
@@ -0,0 +1,40 @@
+port module Users exposing (main)
+
+import Http
+import Json.Decode
+import Json.Encode
+
+
+port renamed : String -> Cmd msg
+
+
+type Msg
+    = Renamed (Result Http.Error String)
+
+
+main : Program String () Msg
+main =
+    Platform.worker
+        { init = \name -> ( (), rename name )
+        , update = update
+        , subscriptions = \_ -> Sub.none
+        }
+
+
+rename : String -> Cmd Msg
+rename name =
+    Http.post
+        { url = "/users/1"
+        , body = Http.jsonBody (Json.Encode.object [ ( "name", Json.Encode.string name ) ])
+        , expect = Http.expectJson Renamed (Json.Decode.field "name" Json.Decode.string)
+        }
+
+
+update : Msg -> () -> ( (), Cmd Msg )
+update msg model =
+    case msg of
+        Renamed (Ok name) ->
+            ( model, renamed name )
+
+        Renamed (Err _) ->
+            ( model, renamed "failed" )
@@ -0,0 +1,24 @@
+module Wire exposing (Message, decoder, encode)
+
+import Bytes exposing (Endianness(..))
+import Bytes.Decode as Decode exposing (Decoder)
+import Bytes.Encode as Encode exposing (Encoder)
+
+
+type alias Message =
+    { id : Int, kind : Int }
+
+
+decoder : Decoder Message
+decoder =
+    Decode.map2 Message
+        (Decode.unsignedInt16 BE)
+        Decode.unsignedInt8
+
+
+encode : Message -> Encoder
+encode { id, kind } =
+    Encode.sequence
+        [ Encode.unsignedInt16 BE id
+        , Encode.unsignedInt8 kind
+        ]
@@ -0,0 +1,29 @@
+port module Worker exposing (main)
+
+import Json.Decode
+import Json.Encode
+
+
+port ready : String -> Cmd msg
+
+
+port save : Json.Encode.Value -> Cmd msg
+
+
+port loaded : (Json.Decode.Value -> msg) -> Sub msg
+
+
+main : Program String Int Json.Decode.Value
+main =
+    Platform.worker
+        { init = \name -> ( 0, ready name )
+        , update = update
+        , subscriptions = \_ -> loaded identity
+        }
+
+
+update : Json.Decode.Value -> Int -> ( Int, Cmd Json.Decode.Value )
+update value count =
+    ( count + 1
+    , save (Json.Encode.object [ ( "count", Json.Encode.int (count + 1) ), ( "value", value ) ])
+    )
@@ -0,0 +1,19 @@
+module Fuzzers exposing (Order, order, quantity)
+
+import Fuzz exposing (Fuzzer)
+
+
+type alias Order =
+    { quantity : Int, note : String }
+
+
+order : Fuzzer Order
+order =
+    Fuzz.map2 Order
+        quantity
+        Fuzz.string
+
+
+quantity : Fuzzer Int
+quantity =
+    Fuzz.intRange 1 100
@@ -1,6 +1,10 @@
 #[cfg(feature = "v8")]
 mod v8 {
-    use elm_rust_binding::{ElmRoot, Result};
+    use std::{convert::Infallible, time::Duration};
+
+    use bytes::Bytes;
+    use elm_rust_binding::{DeadEnd, ElmRoot, Error, HtmlElement, HtmlNode, Navigation, Result};
+    use http_body_util::{BodyExt, Full};
     use serde::{Deserialize, Serialize};
 
     macro_rules! add5_test {
@@ -90,100 +94,1681 @@ mod v8 {
         );
         Ok(())
     }
-}
 
-#[cfg(feature = "quickjs")]
-mod quickjs {
-    use elm_rust_binding::{ElmRoot, Result};
-    use serde::{Deserialize, Serialize};
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn flattened_structs() -> Result<()> {
+        #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+        struct Flattened {
+            a: Option<i32>,
+        }
+        #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+        struct StructIn {
+            #[serde(flatten)]
+            flattened: Flattened,
+            b: Vec<bool>,
+        }
+        #[derive(Deserialize, PartialEq, Eq, Debug, schemars::JsonSchema)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
 
-    macro_rules! add5_test {
-        ($int_type:ty) => {{
-            let elm_root = ElmRoot::new("./tests/elm/src")?;
-            let elm_add5 = elm_root.prepare("Test.add5").await?;
-            let result: $int_type = elm_add5.call(1).await?;
-            assert_eq!(result, 6);
-            Ok(())
-        }};
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_some_struct_mapper = elm_root.prepare_with_schema("Test.someStructMapper")?;
+        let result: Vec<StructOut> = elm_some_struct_mapper.call(vec![StructIn {
+            flattened: Flattened { a: Some(5) },
+            b: vec![true, false],
+        }])?;
+        assert_eq!(
+            result,
+            vec![StructOut {
+                c: vec![5],
+                d: Some(true)
+            }]
+        );
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn i8() -> Result<()> {
-        add5_test!(i8)
+    #[test]
+    fn multiple_arguments() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_repeat = elm_root.prepare2("Test.repeat")?;
+        let result: String = elm_repeat.call((3, "ab".to_owned()))?;
+        assert_eq!(result, "ababab");
+        let elm_clamp = elm_root.prepare3("Test.clampInt")?;
+        let result: i32 = elm_clamp.call((0, 10, 42))?;
+        assert_eq!(result, 10);
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn i16() -> Result<()> {
-        add5_test!(i16)
+    #[test]
+    fn value() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let result: StructOut = elm_root.value("Test.defaultStruct")?;
+        assert_eq!(
+            result,
+            StructOut {
+                c: vec![1, 2, 3],
+                d: None
+            }
+        );
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn i32() -> Result<()> {
-        add5_test!(i32)
+    #[test]
+    fn expressions() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_sum =
+            elm_root.prepare_expr("\\xs -> List.sum (List.map Test.add5 xs)", &["Test"])?;
+        let result: i32 = elm_sum.call(vec![1, 2, 3])?;
+        assert_eq!(result, 21);
+        let elm_multiline = elm_root.prepare_expr(
+            "\\x ->\n    let\n        y = Test.add5 x\n    in\n    y * 2",
+            &["Test"],
+        )?;
+        let result: i32 = elm_multiline.call(1)?;
+        assert_eq!(result, 12);
+        let result: String = elm_root.eval("String.reverse \"abc\"")?;
+        assert_eq!(result, "cba");
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn i64() -> Result<()> {
-        add5_test!(i64)
+    #[test]
+    fn private_function() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.expose_private();
+        let elm_triple = elm_root.prepare("Private.triple")?;
+        let result: i32 = elm_triple.call(2)?;
+        assert_eq!(result, 6);
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn u8() -> Result<()> {
-        add5_test!(u8)
+    #[test]
+    fn decoder() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_decoder = elm_root.prepare_decoder("Test.structOutDecoder")?;
+        let result: StructOut = elm_decoder.call(r#"{ "numbers": [1, 2], "flag": true }"#)?;
+        assert_eq!(
+            result,
+            StructOut {
+                c: vec![1, 2],
+                d: Some(true)
+            }
+        );
+        let result = elm_decoder.call(serde_json::json!({ "numbers": [] }))?;
+        assert_eq!(result, StructOut { c: vec![], d: None });
+        let error = elm_decoder.call(r#"{ "numbers": "1" }"#).unwrap_err();
+        assert!(matches!(*error, Error::ElmDecodeError(_)));
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn u16() -> Result<()> {
-        add5_test!(u16)
+    #[test]
+    fn encoder() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct StructIn {
+            a: Option<i32>,
+            b: Vec<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_encoder = elm_root.prepare_encoder("Test.encodeStructIn")?;
+        let result = elm_encoder.call(StructIn {
+            a: None,
+            b: vec![true],
+        })?;
+        assert_eq!(
+            result,
+            serde_json::json!({ "maybeNumber": null, "flags": [true] })
+        );
+        assert_eq!(
+            elm_encoder.call_to_string(StructIn {
+                a: Some(1),
+                b: vec![],
+            })?,
+            r#"{"maybeNumber":1,"flags":[]}"#
+        );
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn u32() -> Result<()> {
-        add5_test!(u32)
+    #[test]
+    fn codec_roundtrip() -> Result<()> {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Numbers {
+            numbers: Vec<i32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            flag: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let values = [
+            Numbers {
+                numbers: vec![1, 2],
+                flag: Some(false),
+            },
+            Numbers {
+                numbers: vec![],
+                flag: None,
+            },
+        ];
+        elm_root.check_codec_roundtrip("Test.structOutDecoder", "Test.encodeStructOut", &values)?;
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn u64() -> Result<()> {
-        add5_test!(u64)
+    #[test]
+    fn parser() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Filter {
+            field: String,
+            value: i32,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_parser = elm_root.prepare_parser("Query.parser")?;
+        let result: std::result::Result<Filter, _> = elm_parser.call("age=42")?;
+        assert_eq!(
+            result,
+            Ok(Filter {
+                field: "age".to_owned(),
+                value: 42
+            })
+        );
+        let dead_ends = elm_parser.call("age:42")?.unwrap_err();
+        assert_eq!(
+            dead_ends,
+            vec![DeadEnd {
+                row: 1,
+                col: 4,
+                problem: "ExpectingSymbol \"=\"".to_owned()
+            }]
+        );
+
+        let quoted_parser = elm_root.prepare_parser::<String>("Query.quoted")?;
+        let dead_ends = quoted_parser.call("text")?.unwrap_err();
+        assert_eq!(dead_ends[0].problem, r#"ExpectingSymbol "\"""#);
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn string() -> Result<()> {
+    #[test]
+    fn bytes() -> Result<()> {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Message {
+            id: u16,
+            kind: u8,
+        }
+
         let elm_root = ElmRoot::new("./tests/elm/src")?;
-        let elm_prepend_test = elm_root.prepare("Test.prependTest").await?;
-        let result: String = elm_prepend_test.call("abc".to_owned()).await?;
-        assert_eq!(result, "testabc");
+        let elm_decoder = elm_root.prepare_bytes_decoder("Wire.decoder")?;
+        let result: Option<Message> = elm_decoder.call(&[1, 2, 3])?;
+        assert_eq!(result, Some(Message { id: 258, kind: 3 }));
+        let result = elm_decoder.call(&[1])?;
+        assert_eq!(result, None);
+        let elm_encoder = elm_root.prepare_bytes_encoder("Wire.encode")?;
+        let result = elm_encoder.call(Message { id: 258, kind: 3 })?;
+        assert_eq!(result, vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn generator() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Level {
+            width: i32,
+            height: i32,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_generator = elm_root.prepare_generator("Gen.level")?;
+        let levels: Vec<Level> = elm_generator.sample(42, 10)?;
+        assert_eq!(levels.len(), 10);
+        assert!(levels
+            .iter()
+            .all(|level| (1..=10).contains(&level.width) && (1..=10).contains(&level.height)));
+        assert_eq!(elm_generator.sample(42, 10)?, levels);
+        // Values are returned in the order they were generated, so fewer values are a prefix of more
+        assert_eq!(elm_generator.sample(42, 1)?, levels[..1]);
+        assert_eq!(elm_generator.sample(42, 3)?, levels[..3]);
+        Ok(())
+    }
+
+    #[test]
+    fn fuzzer() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Order {
+            quantity: i32,
+            note: String,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_fuzzer = elm_root.prepare_fuzzer("Fuzzers.order")?;
+        let orders: Vec<Order> = elm_fuzzer.sample(7, 20)?;
+        assert_eq!(orders.len(), 20);
+        assert!(orders
+            .iter()
+            .all(|order| (1..=100).contains(&order.quantity)));
+        let first_orders: Vec<Order> = elm_fuzzer.sample(7, 4)?;
+        assert_eq!(first_orders, orders[..4]);
+
+        let elm_fuzzer = elm_root.prepare_fuzzer("Fuzzers.quantity")?;
+        let quantities: Vec<i32> = elm_fuzzer.sample(7, 20)?;
+        let index = (1..quantities.len())
+            .find(|&index| quantities[index] > 1 && quantities[index] < quantities[0])
+            .expect("a shrinkable quantity smaller than the first one");
+        let candidates = elm_fuzzer.shrink(7, index, 5)?;
+        assert!((1..=5).contains(&candidates.len()));
+        assert!(candidates
+            .iter()
+            .all(|candidate| (1..quantities[index]).contains(candidate)));
+        assert_ne!(candidates, elm_fuzzer.shrink(7, 0, 5)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn url_parser() -> Result<()> {
+        #[derive(Deserialize, elm_rust_binding::ElmType, PartialEq, Debug)]
+        #[elm(module = "Route")]
+        enum Route {
+            Home,
+            User(i32),
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_route = elm_root.prepare_url_parser("Route.fromUrl")?;
+        let result: Option<Route> = elm_route.call("https://example.com/users/42")?;
+        assert_eq!(result, Some(Route::User(42)));
+        let result = elm_route.call("/users/42")?;
+        assert_eq!(result, Some(Route::User(42)));
+        let result = elm_route.call("https://example.com/")?;
+        assert_eq!(result, Some(Route::Home));
+        let result = elm_route.call("https://example.com/unknown")?;
+        assert_eq!(result, None);
+        let error = elm_route.call("not a url").unwrap_err();
+        assert!(matches!(*error, Error::InvalidUrl(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn elm_tests() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm-test/src")?;
+        let report = elm_root.run_elm_tests("tests/")?;
+        assert_eq!(report.results.len(), 4);
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].labels,
+            ["MathTest.clampSuite", "clampInt", "above the range"]
+        );
+        assert!(!report.passed());
+        Ok(())
+    }
+
+    #[test]
+    fn worker() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Saved {
+            count: i32,
+            value: serde_json::Value,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Worker")?;
+        let worker = worker_handle.init("test")?;
+        let ready = worker.receiver::<String>("ready")?;
+        assert_eq!(ready.try_iter().collect::<Vec<_>>(), ["test"]);
+        let saved = worker.receiver::<Saved>("save")?;
+        let loaded = worker.sender("loaded")?;
+        loaded.send(serde_json::json!({ "id": 1 }))?;
+        loaded.send(serde_json::json!("second"))?;
+        assert_eq!(
+            saved.try_iter().collect::<Vec<_>>(),
+            [
+                Saved {
+                    count: 1,
+                    value: serde_json::json!({ "id": 1 })
+                },
+                Saved {
+                    count: 2,
+                    value: serde_json::json!("second")
+                }
+            ]
+        );
+        let error = worker.sender::<i32>("save").err().unwrap();
+        assert!(matches!(*error, Error::UnknownPort(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn port_message_during_init() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Worker")?;
+        let worker = worker_handle.init("during init".to_owned())?;
+        // `ready` is sent by `init`, before any value was sent to the worker
+        let ready = worker.receiver::<String>("ready")?;
+        assert_eq!(ready.try_recv().ok(), Some("during init".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn port_handler() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Backend")?;
+        let worker = worker_handle.init(())?;
+        let done = worker.receiver::<Vec<String>>("done")?;
+        worker.handle_port("askBackend", "backendReplied", |id: i32| {
+            format!("user {id}")
+        })?;
+        assert_eq!(
+            done.try_recv().ok(),
+            Some(vec![
+                "user 1".to_owned(),
+                "user 2".to_owned(),
+                "user 3".to_owned()
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn async_port_handler() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Backend")?;
+        let worker = worker_handle.init(())?;
+        let done = worker.receiver::<Vec<String>>("done")?;
+        worker.handle_port_async("askBackend", "backendReplied", |id: i32| async move {
+            tokio::task::yield_now().await;
+            format!("user {id}")
+        })?;
+        assert_eq!(
+            done.try_recv().ok(),
+            Some(vec![
+                "user 1".to_owned(),
+                "user 2".to_owned(),
+                "user 3".to_owned()
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn task() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.start_time(Duration::from_secs(60));
+        let delayed_double = elm_root.prepare_task::<i32, i32, String>("Tasks.delayedDouble")?;
+        assert_eq!(delayed_double.call(21)?, Ok(42));
+        let divide = elm_root.prepare_task::<(i32, i32), i32, String>("Tasks.divide")?;
+        assert_eq!(divide.call((7, 2))?, Ok(3));
+        assert_eq!(divide.call((7, 0))?, Err("division by zero".to_owned()));
+        // Errors encoded as `null` are not mistaken for a success
+        let positive = elm_root.prepare_task::<i32, i32, ()>("Tasks.positive")?;
+        assert_eq!(positive.call(3)?, Ok(3));
+        assert_eq!(positive.call(-3)?, Err(()));
+        let sleeping_now = elm_root.prepare_task::<f64, i64, String>("Tasks.sleepingNow")?;
+        // Every call starts at the start time again
+        assert_eq!(sleeping_now.call(500.0)?, Ok(60_500));
+        assert_eq!(sleeping_now.call(500.0)?, Ok(60_500));
+        Ok(())
+    }
+
+    #[test]
+    fn advance() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.start_time(Duration::from_secs(60));
+        let ticker_handle = elm_root.prepare_worker("Ticker")?;
+        let ticker = ticker_handle.init(())?;
+        let ticked = ticker.receiver::<i64>("ticked")?;
+        assert!(ticked.try_recv().is_err());
+        ticker.advance(Duration::from_millis(2500))?;
+        assert_eq!(ticked.try_iter().collect::<Vec<_>>(), [61_000, 62_000]);
+        ticker.advance(Duration::from_millis(500))?;
+        assert_eq!(ticked.try_iter().collect::<Vec<_>>(), [63_000]);
+        Ok(())
+    }
+
+    /// A stand-in for the backend: `GET /users/1` returns the user, `POST /users/1` echoes the renamed user.
+    async fn users_backend(
+        request: http::Request<Full<Bytes>>,
+    ) -> std::result::Result<http::Response<Full<Bytes>>, Infallible> {
+        let (parts, body) = request.into_parts();
+        let response = match (parts.method.as_str(), parts.uri.path()) {
+            ("GET", "/users/1") => http::Response::new(Full::from(r#"{ "name": "Alice" }"#)),
+            ("POST", "/users/1") => {
+                http::Response::new(Full::new(body.collect().await?.to_bytes()))
+            }
+            _ => http::Response::builder()
+                .status(404)
+                .body(Full::default())
+                .unwrap(),
+        };
+        Ok(response)
+    }
+
+    #[test]
+    fn http_task() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let fetch_user_name = elm_root.prepare_task::<i32, String, String>("Api.fetchUserName")?;
+        assert_eq!(fetch_user_name.call(1)?, Ok("Alice".to_owned()));
+        assert_eq!(fetch_user_name.call(2)?, Err("status 404".to_owned()));
+        Ok(())
+    }
+
+    #[test]
+    fn http_worker() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let users = elm_root.prepare_worker("Users")?;
+        let worker = users.init("Bob")?;
+        let renamed = worker.receiver::<String>("renamed")?;
+        assert_eq!(renamed.try_iter().collect::<Vec<_>>(), ["Bob"]);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn http_task_within_tokio_runtime() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let fetch_user_name = elm_root.prepare_task::<i32, String, String>("Api.fetchUserName")?;
+        assert_eq!(fetch_user_name.call(1)?, Ok("Alice".to_owned()));
         Ok(())
     }
 
     #[tokio::test]
-    async fn structs() -> Result<()> {
+    async fn http_task_within_current_thread_runtime() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let fetch_user_name = elm_root.prepare_task::<i32, String, String>("Api.fetchUserName")?;
+        let error = fetch_user_name.call(1).unwrap_err();
+        assert!(matches!(*error, Error::AsyncRuntimeError(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn http_without_service() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let fetch_user_name = elm_root.prepare_task::<i32, String, String>("Api.fetchUserName")?;
+        let error = fetch_user_name.call(1).unwrap_err();
+        assert!(matches!(*error, Error::HttpServiceError(_)));
+        Ok(())
+    }
+
+    #[test]
+    fn view() -> Result<()> {
         #[derive(Serialize, Deserialize)]
-        struct StructIn {
-            a: Option<i32>,
-            b: Vec<bool>,
+        struct User {
+            name: String,
+            admin: bool,
         }
-        #[derive(Deserialize, PartialEq, Eq, Debug)]
-        struct StructOut {
-            c: Vec<i32>,
-            d: Option<bool>,
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let page_view = elm_root.prepare_view("Page.view")?;
+        let html = page_view.call(User {
+            name: "Alice & Bob".to_owned(),
+            admin: false,
+        })?;
+        assert_eq!(
+            html.to_string(),
+            r#"<div class="user" style="color: red"><h1>Hello, Alice &amp; Bob</h1><input disabled value="Alice &amp; Bob"></div>"#
+        );
+        let HtmlNode::Element(div) = page_view.call(User {
+            name: "Carol".to_owned(),
+            admin: true,
+        })?
+        else {
+            panic!("Expected an element");
+        };
+        assert_eq!(
+            div.children[0],
+            HtmlNode::Element(HtmlElement {
+                tag: "h1".to_owned(),
+                attributes: Default::default(),
+                children: vec![HtmlNode::Text("Hello, Carol".to_owned())],
+            })
+        );
+        assert_eq!(div.children[1].to_string(), r#"<input value="Carol">"#);
+        Ok(())
+    }
+
+    #[test]
+    fn query_view() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct User {
+            name: String,
+            admin: bool,
         }
 
         let elm_root = ElmRoot::new("./tests/elm/src")?;
-        let elm_some_struct_mapper = elm_root.prepare("Test.someStructMapper").await?;
-        let result: Vec<StructOut> = elm_some_struct_mapper
-            .call(vec![StructIn {
-                a: Some(5),
-                b: vec![true, false],
-            }])
-            .await?;
+        let page_view = elm_root.prepare_view("Page.view")?;
+        let html = page_view.call(User {
+            name: "Alice".to_owned(),
+            admin: false,
+        })?;
+        assert_eq!(html.find("div.user > h1")?.text(), "Hello, Alice");
         assert_eq!(
-            result,
-            vec![StructOut {
-                c: vec![5],
-                d: Some(true)
-            }]
+            html.find("input[disabled]")?.attribute("value"),
+            Some("Alice")
+        );
+        assert_eq!(html.find("div.user")?.find("*")?.count(), 2);
+        assert!(matches!(
+            *html.find("h1:first-child").unwrap_err(),
+            Error::InvalidSelector(_)
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn element() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Signup")]
+        enum Msg {
+            NameChanged(String),
+            Submitted,
+            Cleared,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(rename_all = "camelCase")]
+        struct Model {
+            name: String,
+            signed_up: Vec<String>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let signup = elm_root.prepare_element::<String, Msg, Model>("Signup")?;
+        let program = signup.init("Alice".to_owned())?;
+        program.submit("form")?;
+        program.input("input[name=name]", "Bob")?;
+        assert_eq!(
+            program.html()?.find("input")?.attribute("value"),
+            Some("Bob")
+        );
+        program.submit("form")?;
+        assert_eq!(
+            program.model()?,
+            Model {
+                name: "".to_owned(),
+                signed_up: vec!["Alice".to_owned(), "Bob".to_owned()]
+            }
+        );
+        let html = program.html()?;
+        assert_eq!(html.find("ul > li")?.count(), 2);
+        assert_eq!(html.find("li")?.text(), "AliceBob");
+        assert!(matches!(
+            *program.click("button").unwrap_err(),
+            Error::NoUniqueElement { count: 2, .. }
+        ));
+        program.click("button.clear")?;
+        assert!(program.html()?.find("li")?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn sandbox() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Clicker")]
+        enum Msg {
+            Clicked,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let clicker = elm_root.prepare_sandbox::<Msg, i32>("Clicker")?;
+        let program = clicker.init(())?;
+        program.click("button")?;
+        program.click("button")?;
+        assert_eq!(program.model()?, 2);
+        assert_eq!(program.html()?.to_string(), "<button>2</button>");
+        Ok(())
+    }
+
+    #[test]
+    fn application() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let shop = elm_root.prepare_application::<()>("Shop")?;
+        let program = shop.init("http://localhost/", ())?;
+        assert_eq!(program.html()?.find("body > h1")?.text(), "/");
+        program.click("a.product")?;
+        assert_eq!(
+            program.take_navigations()?,
+            [Navigation::PushUrl {
+                url: "http://localhost/products/7".to_owned()
+            }]
+        );
+        assert_eq!(program.url()?, "http://localhost/products/7");
+        assert_eq!(program.html()?.find("h1")?.text(), "/products/7");
+        program.click("a.help")?;
+        assert_eq!(
+            program.take_navigations()?,
+            [Navigation::Load {
+                url: "https://example.org/help".to_owned()
+            }]
+        );
+        program.change_url("/cart")?;
+        assert_eq!(program.html()?.find("h1")?.text(), "/cart");
+        program.back()?;
+        assert_eq!(program.html()?.find("h1")?.text(), "/products/7");
+        assert!(program.take_navigations()?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn program() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Counter")]
+        enum Msg {
+            Increment,
+            Add(i32),
+            Save,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Model {
+            count: i32,
+            history: Vec<i32>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let counter = elm_root.prepare_program::<i32, Msg, Model>("Counter")?;
+        let program = counter.init(5)?;
+        program.send(Msg::Increment)?;
+        program.send(Msg::Add(3))?;
+        program.send(Msg::Save)?;
+        assert_eq!(
+            program.model()?,
+            Model {
+                count: 9,
+                history: vec![9]
+            }
+        );
+        assert_eq!(
+            program.take_port_messages()?,
+            [elm_rust_binding::PortMessage {
+                port: "saved".to_owned(),
+                value: serde_json::json!(9)
+            }]
+        );
+        assert!(program.take_port_messages()?.is_empty());
+        // Instances do not share their model
+        assert_eq!(counter.init(0)?.model()?.count, 0);
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn update() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Counter")]
+        enum Msg {
+            Increment,
+            Add(i32),
+            Save,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Model {
+            count: i32,
+            history: Vec<i32>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_update = elm_root.prepare_update("Counter.update")?;
+        let initial = Model {
+            count: 0,
+            history: vec![],
+        };
+        let models: Vec<Model> =
+            elm_update.call((initial, vec![Msg::Add(2), Msg::Save, Msg::Increment]))?;
+        assert_eq!(
+            models,
+            [
+                Model {
+                    count: 2,
+                    history: vec![]
+                },
+                Model {
+                    count: 2,
+                    history: vec![2]
+                },
+                Model {
+                    count: 3,
+                    history: vec![2]
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derived_custom_type() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType, PartialEq, Debug)]
+        #[elm(module = "Test")]
+        enum Shape {
+            Circle(f64),
+            Rectangle { width: f64, height: f64 },
+            Empty,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_scale = elm_root.prepare("Test.scale")?;
+        let result: Shape = elm_scale.call(Shape::Rectangle {
+            width: 1.5,
+            height: 2.0,
+        })?;
+        assert_eq!(
+            result,
+            Shape::Rectangle {
+                width: 3.0,
+                height: 4.0
+            }
+        );
+        let result: Shape = elm_scale.call(Shape::Empty)?;
+        assert_eq!(result, Shape::Empty);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "quickjs")]
+mod quickjs {
+    use std::{convert::Infallible, time::Duration};
+
+    use bytes::Bytes;
+    use elm_rust_binding::{DeadEnd, ElmRoot, Error, HtmlElement, HtmlNode, Navigation, Result};
+    use http_body_util::{BodyExt, Full};
+    use serde::{Deserialize, Serialize};
+
+    macro_rules! add5_test {
+        ($int_type:ty) => {{
+            let elm_root = ElmRoot::new("./tests/elm/src")?;
+            let elm_add5 = elm_root.prepare("Test.add5").await?;
+            let result: $int_type = elm_add5.call(1).await?;
+            assert_eq!(result, 6);
+            Ok(())
+        }};
+    }
+
+    #[tokio::test]
+    async fn i8() -> Result<()> {
+        add5_test!(i8)
+    }
+
+    #[tokio::test]
+    async fn i16() -> Result<()> {
+        add5_test!(i16)
+    }
+
+    #[tokio::test]
+    async fn i32() -> Result<()> {
+        add5_test!(i32)
+    }
+
+    #[tokio::test]
+    async fn i64() -> Result<()> {
+        add5_test!(i64)
+    }
+
+    #[tokio::test]
+    async fn u8() -> Result<()> {
+        add5_test!(u8)
+    }
+
+    #[tokio::test]
+    async fn u16() -> Result<()> {
+        add5_test!(u16)
+    }
+
+    #[tokio::test]
+    async fn u32() -> Result<()> {
+        add5_test!(u32)
+    }
+
+    #[tokio::test]
+    async fn u64() -> Result<()> {
+        add5_test!(u64)
+    }
+
+    #[tokio::test]
+    async fn string() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_prepend_test = elm_root.prepare("Test.prependTest").await?;
+        let result: String = elm_prepend_test.call("abc".to_owned()).await?;
+        assert_eq!(result, "testabc");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn structs() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct StructIn {
+            a: Option<i32>,
+            b: Vec<bool>,
+        }
+        #[derive(Deserialize, PartialEq, Eq, Debug)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_some_struct_mapper = elm_root.prepare("Test.someStructMapper").await?;
+        let result: Vec<StructOut> = elm_some_struct_mapper
+            .call(vec![StructIn {
+                a: Some(5),
+                b: vec![true, false],
+            }])
+            .await?;
+        assert_eq!(
+            result,
+            vec![StructOut {
+                c: vec![5],
+                d: Some(true)
+            }]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "schemars")]
+    #[tokio::test]
+    async fn flattened_structs() -> Result<()> {
+        #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+        struct Flattened {
+            a: Option<i32>,
+        }
+        #[derive(Serialize, Deserialize, schemars::JsonSchema)]
+        struct StructIn {
+            #[serde(flatten)]
+            flattened: Flattened,
+            b: Vec<bool>,
+        }
+        #[derive(Deserialize, PartialEq, Eq, Debug, schemars::JsonSchema)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_some_struct_mapper = elm_root
+            .prepare_with_schema("Test.someStructMapper")
+            .await?;
+        let result: Vec<StructOut> = elm_some_struct_mapper
+            .call(vec![StructIn {
+                flattened: Flattened { a: Some(5) },
+                b: vec![true, false],
+            }])
+            .await?;
+        assert_eq!(
+            result,
+            vec![StructOut {
+                c: vec![5],
+                d: Some(true)
+            }]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn multiple_arguments() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_repeat = elm_root.prepare2("Test.repeat").await?;
+        let result: String = elm_repeat.call((3, "ab".to_owned())).await?;
+        assert_eq!(result, "ababab");
+        let elm_clamp = elm_root.prepare3("Test.clampInt").await?;
+        let result: i32 = elm_clamp.call((0, 10, 42)).await?;
+        assert_eq!(result, 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn value() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let result: StructOut = elm_root.value("Test.defaultStruct").await?;
+        assert_eq!(
+            result,
+            StructOut {
+                c: vec![1, 2, 3],
+                d: None
+            }
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn expressions() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_sum = elm_root
+            .prepare_expr("\\xs -> List.sum (List.map Test.add5 xs)", &["Test"])
+            .await?;
+        let result: i32 = elm_sum.call(vec![1, 2, 3]).await?;
+        assert_eq!(result, 21);
+        let elm_multiline = elm_root
+            .prepare_expr(
+                "\\x ->\n    let\n        y = Test.add5 x\n    in\n    y * 2",
+                &["Test"],
+            )
+            .await?;
+        let result: i32 = elm_multiline.call(1).await?;
+        assert_eq!(result, 12);
+        let result: String = elm_root.eval("String.reverse \"abc\"").await?;
+        assert_eq!(result, "cba");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn private_function() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.expose_private();
+        let elm_triple = elm_root.prepare("Private.triple").await?;
+        let result: i32 = elm_triple.call(2).await?;
+        assert_eq!(result, 6);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn decoder() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct StructOut {
+            c: Vec<i32>,
+            d: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_decoder = elm_root.prepare_decoder("Test.structOutDecoder").await?;
+        let result: StructOut = elm_decoder
+            .call(r#"{ "numbers": [1, 2], "flag": true }"#)
+            .await?;
+        assert_eq!(
+            result,
+            StructOut {
+                c: vec![1, 2],
+                d: Some(true)
+            }
+        );
+        let result = elm_decoder
+            .call(serde_json::json!({ "numbers": [] }))
+            .await?;
+        assert_eq!(result, StructOut { c: vec![], d: None });
+        let error = elm_decoder.call(r#"{ "numbers": "1" }"#).await.unwrap_err();
+        assert!(matches!(*error, Error::ElmDecodeError(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encoder() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct StructIn {
+            a: Option<i32>,
+            b: Vec<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_encoder = elm_root.prepare_encoder("Test.encodeStructIn").await?;
+        let result = elm_encoder
+            .call(StructIn {
+                a: None,
+                b: vec![true],
+            })
+            .await?;
+        assert_eq!(
+            result,
+            serde_json::json!({ "maybeNumber": null, "flags": [true] })
+        );
+        assert_eq!(
+            elm_encoder
+                .call_to_string(StructIn {
+                    a: Some(1),
+                    b: vec![],
+                })
+                .await?,
+            r#"{"maybeNumber":1,"flags":[]}"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn codec_roundtrip() -> Result<()> {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Numbers {
+            numbers: Vec<i32>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            flag: Option<bool>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let values = [
+            Numbers {
+                numbers: vec![1, 2],
+                flag: Some(false),
+            },
+            Numbers {
+                numbers: vec![],
+                flag: None,
+            },
+        ];
+        elm_root
+            .check_codec_roundtrip("Test.structOutDecoder", "Test.encodeStructOut", &values)
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parser() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Filter {
+            field: String,
+            value: i32,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_parser = elm_root.prepare_parser("Query.parser").await?;
+        let result: std::result::Result<Filter, _> = elm_parser.call("age=42").await?;
+        assert_eq!(
+            result,
+            Ok(Filter {
+                field: "age".to_owned(),
+                value: 42
+            })
+        );
+        let dead_ends = elm_parser.call("age:42").await?.unwrap_err();
+        assert_eq!(
+            dead_ends,
+            vec![DeadEnd {
+                row: 1,
+                col: 4,
+                problem: "ExpectingSymbol \"=\"".to_owned()
+            }]
+        );
+
+        let quoted_parser = elm_root.prepare_parser::<String>("Query.quoted").await?;
+        let dead_ends = quoted_parser.call("text").await?.unwrap_err();
+        assert_eq!(dead_ends[0].problem, r#"ExpectingSymbol "\"""#);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bytes() -> Result<()> {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Message {
+            id: u16,
+            kind: u8,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_decoder = elm_root.prepare_bytes_decoder("Wire.decoder").await?;
+        let result: Option<Message> = elm_decoder.call(&[1, 2, 3]).await?;
+        assert_eq!(result, Some(Message { id: 258, kind: 3 }));
+        let result = elm_decoder.call(&[1]).await?;
+        assert_eq!(result, None);
+        let elm_encoder = elm_root.prepare_bytes_encoder("Wire.encode").await?;
+        let result = elm_encoder.call(Message { id: 258, kind: 3 }).await?;
+        assert_eq!(result, vec![1, 2, 3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn generator() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Level {
+            width: i32,
+            height: i32,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_generator = elm_root.prepare_generator("Gen.level").await?;
+        let levels: Vec<Level> = elm_generator.sample(42, 10).await?;
+        assert_eq!(levels.len(), 10);
+        assert!(levels
+            .iter()
+            .all(|level| (1..=10).contains(&level.width) && (1..=10).contains(&level.height)));
+        assert_eq!(elm_generator.sample(42, 10).await?, levels);
+        // Values are returned in the order they were generated, so fewer values are a prefix of more
+        assert_eq!(elm_generator.sample(42, 1).await?, levels[..1]);
+        assert_eq!(elm_generator.sample(42, 3).await?, levels[..3]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fuzzer() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Order {
+            quantity: i32,
+            note: String,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_fuzzer = elm_root.prepare_fuzzer("Fuzzers.order").await?;
+        let orders: Vec<Order> = elm_fuzzer.sample(7, 20).await?;
+        assert_eq!(orders.len(), 20);
+        assert!(orders
+            .iter()
+            .all(|order| (1..=100).contains(&order.quantity)));
+        let first_orders: Vec<Order> = elm_fuzzer.sample(7, 4).await?;
+        assert_eq!(first_orders, orders[..4]);
+
+        let elm_fuzzer = elm_root.prepare_fuzzer("Fuzzers.quantity").await?;
+        let quantities: Vec<i32> = elm_fuzzer.sample(7, 20).await?;
+        let index = (1..quantities.len())
+            .find(|&index| quantities[index] > 1 && quantities[index] < quantities[0])
+            .expect("a shrinkable quantity smaller than the first one");
+        let candidates = elm_fuzzer.shrink(7, index, 5).await?;
+        assert!((1..=5).contains(&candidates.len()));
+        assert!(candidates
+            .iter()
+            .all(|candidate| (1..quantities[index]).contains(candidate)));
+        assert_ne!(candidates, elm_fuzzer.shrink(7, 0, 5).await?);
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn url_parser() -> Result<()> {
+        #[derive(Deserialize, elm_rust_binding::ElmType, PartialEq, Debug)]
+        #[elm(module = "Route")]
+        enum Route {
+            Home,
+            User(i32),
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_route = elm_root.prepare_url_parser("Route.fromUrl").await?;
+        let result: Option<Route> = elm_route.call("https://example.com/users/42").await?;
+        assert_eq!(result, Some(Route::User(42)));
+        let result = elm_route.call("/users/42").await?;
+        assert_eq!(result, Some(Route::User(42)));
+        let result = elm_route.call("https://example.com/").await?;
+        assert_eq!(result, Some(Route::Home));
+        let result = elm_route.call("https://example.com/unknown").await?;
+        assert_eq!(result, None);
+        let error = elm_route.call("not a url").await.unwrap_err();
+        assert!(matches!(*error, Error::InvalidUrl(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn elm_tests() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm-test/src")?;
+        let report = elm_root.run_elm_tests("tests/").await?;
+        assert_eq!(report.results.len(), 4);
+        let failures = report.failures().collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(
+            failures[0].labels,
+            ["MathTest.clampSuite", "clampInt", "above the range"]
+        );
+        assert!(!report.passed());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn worker() -> Result<()> {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Saved {
+            count: i32,
+            value: serde_json::Value,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Worker").await?;
+        let worker = worker_handle.init("test").await?;
+        let ready = worker.receiver::<String>("ready")?;
+        assert_eq!(ready.try_iter().collect::<Vec<_>>(), ["test"]);
+        let saved = worker.receiver::<Saved>("save")?;
+        let loaded = worker.sender("loaded")?;
+        loaded.send(serde_json::json!({ "id": 1 })).await?;
+        loaded.send(serde_json::json!("second")).await?;
+        assert_eq!(
+            saved.try_iter().collect::<Vec<_>>(),
+            [
+                Saved {
+                    count: 1,
+                    value: serde_json::json!({ "id": 1 })
+                },
+                Saved {
+                    count: 2,
+                    value: serde_json::json!("second")
+                }
+            ]
+        );
+        let error = worker.sender::<i32>("save").err().unwrap();
+        assert!(matches!(*error, Error::UnknownPort(_)));
+        worker.dispose().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn port_message_during_init() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Worker").await?;
+        let worker = worker_handle.init("during init".to_owned()).await?;
+        // `ready` is sent by `init`, before any value was sent to the worker
+        let ready = worker.receiver::<String>("ready")?;
+        assert_eq!(ready.try_recv().ok(), Some("during init".to_owned()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn port_handler() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let worker_handle = elm_root.prepare_worker("Backend").await?;
+        let worker = worker_handle.init(()).await?;
+        let done = worker.receiver::<Vec<String>>("done")?;
+        worker
+            .handle_port_async("askBackend", "backendReplied", |id: i32| async move {
+                format!("user {id}")
+            })
+            .await?;
+        assert_eq!(
+            done.try_recv().ok(),
+            Some(vec![
+                "user 1".to_owned(),
+                "user 2".to_owned(),
+                "user 3".to_owned()
+            ])
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn task() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.start_time(Duration::from_secs(60));
+        let delayed_double = elm_root
+            .prepare_task::<i32, i32, String>("Tasks.delayedDouble")
+            .await?;
+        assert_eq!(delayed_double.call(21).await?, Ok(42));
+        let divide = elm_root
+            .prepare_task::<(i32, i32), i32, String>("Tasks.divide")
+            .await?;
+        assert_eq!(divide.call((7, 2)).await?, Ok(3));
+        assert_eq!(
+            divide.call((7, 0)).await?,
+            Err("division by zero".to_owned())
+        );
+        // Errors encoded as `null` are not mistaken for a success
+        let positive = elm_root
+            .prepare_task::<i32, i32, ()>("Tasks.positive")
+            .await?;
+        assert_eq!(positive.call(3).await?, Ok(3));
+        assert_eq!(positive.call(-3).await?, Err(()));
+        let sleeping_now = elm_root
+            .prepare_task::<f64, i64, String>("Tasks.sleepingNow")
+            .await?;
+        // Every call starts at the start time again
+        assert_eq!(sleeping_now.call(500.0).await?, Ok(60_500));
+        assert_eq!(sleeping_now.call(500.0).await?, Ok(60_500));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn advance() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?.start_time(Duration::from_secs(60));
+        let ticker_handle = elm_root.prepare_worker("Ticker").await?;
+        let ticker = ticker_handle.init(()).await?;
+        let ticked = ticker.receiver::<i64>("ticked")?;
+        assert!(ticked.try_recv().is_err());
+        ticker.advance(Duration::from_millis(2500)).await?;
+        assert_eq!(ticked.try_iter().collect::<Vec<_>>(), [61_000, 62_000]);
+        ticker.advance(Duration::from_millis(500)).await?;
+        assert_eq!(ticked.try_iter().collect::<Vec<_>>(), [63_000]);
+        Ok(())
+    }
+
+    /// A stand-in for the backend: `GET /users/1` returns the user, `POST /users/1` echoes the renamed user.
+    async fn users_backend(
+        request: http::Request<Full<Bytes>>,
+    ) -> std::result::Result<http::Response<Full<Bytes>>, Infallible> {
+        let (parts, body) = request.into_parts();
+        let response = match (parts.method.as_str(), parts.uri.path()) {
+            ("GET", "/users/1") => http::Response::new(Full::from(r#"{ "name": "Alice" }"#)),
+            ("POST", "/users/1") => {
+                http::Response::new(Full::new(body.collect().await?.to_bytes()))
+            }
+            _ => http::Response::builder()
+                .status(404)
+                .body(Full::default())
+                .unwrap(),
+        };
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn http_task() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let fetch_user_name = elm_root
+            .prepare_task::<i32, String, String>("Api.fetchUserName")
+            .await?;
+        assert_eq!(fetch_user_name.call(1).await?, Ok("Alice".to_owned()));
+        assert_eq!(fetch_user_name.call(2).await?, Err("status 404".to_owned()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn http_worker() -> Result<()> {
+        let elm_root =
+            ElmRoot::new("./tests/elm/src")?.http_service(tower::service_fn(users_backend));
+        let users = elm_root.prepare_worker("Users").await?;
+        let worker = users.init("Bob").await?;
+        let renamed = worker.receiver::<String>("renamed")?;
+        assert_eq!(renamed.try_iter().collect::<Vec<_>>(), ["Bob"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn http_without_service() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let fetch_user_name = elm_root
+            .prepare_task::<i32, String, String>("Api.fetchUserName")
+            .await?;
+        let error = fetch_user_name.call(1).await.unwrap_err();
+        assert!(matches!(*error, Error::HttpServiceError(_)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn view() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct User {
+            name: String,
+            admin: bool,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let page_view = elm_root.prepare_view("Page.view").await?;
+        let html = page_view
+            .call(User {
+                name: "Alice & Bob".to_owned(),
+                admin: false,
+            })
+            .await?;
+        assert_eq!(
+            html.to_string(),
+            r#"<div class="user" style="color: red"><h1>Hello, Alice &amp; Bob</h1><input disabled value="Alice &amp; Bob"></div>"#
+        );
+        let HtmlNode::Element(div) = page_view
+            .call(User {
+                name: "Carol".to_owned(),
+                admin: true,
+            })
+            .await?
+        else {
+            panic!("Expected an element");
+        };
+        assert_eq!(
+            div.children[0],
+            HtmlNode::Element(HtmlElement {
+                tag: "h1".to_owned(),
+                attributes: Default::default(),
+                children: vec![HtmlNode::Text("Hello, Carol".to_owned())],
+            })
+        );
+        assert_eq!(div.children[1].to_string(), r#"<input value="Carol">"#);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_view() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct User {
+            name: String,
+            admin: bool,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let page_view = elm_root.prepare_view("Page.view").await?;
+        let html = page_view
+            .call(User {
+                name: "Alice".to_owned(),
+                admin: false,
+            })
+            .await?;
+        assert_eq!(html.find("div.user > h1")?.text(), "Hello, Alice");
+        assert_eq!(
+            html.find("input[disabled]")?.attribute("value"),
+            Some("Alice")
+        );
+        assert_eq!(html.find("div.user")?.find("*")?.count(), 2);
+        assert!(matches!(
+            *html.find("h1:first-child").unwrap_err(),
+            Error::InvalidSelector(_)
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn element() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Signup")]
+        enum Msg {
+            NameChanged(String),
+            Submitted,
+            Cleared,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(rename_all = "camelCase")]
+        struct Model {
+            name: String,
+            signed_up: Vec<String>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let signup = elm_root
+            .prepare_element::<String, Msg, Model>("Signup")
+            .await?;
+        let program = signup.init("Alice".to_owned()).await?;
+        program.submit("form").await?;
+        program.input("input[name=name]", "Bob").await?;
+        assert_eq!(
+            program.html().await?.find("input")?.attribute("value"),
+            Some("Bob")
+        );
+        program.submit("form").await?;
+        assert_eq!(
+            program.model().await?,
+            Model {
+                name: "".to_owned(),
+                signed_up: vec!["Alice".to_owned(), "Bob".to_owned()]
+            }
+        );
+        let html = program.html().await?;
+        assert_eq!(html.find("ul > li")?.count(), 2);
+        assert_eq!(html.find("li")?.text(), "AliceBob");
+        assert!(matches!(
+            *program.click("button").await.unwrap_err(),
+            Error::NoUniqueElement { count: 2, .. }
+        ));
+        program.click("button.clear").await?;
+        assert!(program.html().await?.find("li")?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn sandbox() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Clicker")]
+        enum Msg {
+            Clicked,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let clicker = elm_root.prepare_sandbox::<Msg, i32>("Clicker").await?;
+        let program = clicker.init(()).await?;
+        program.click("button").await?;
+        program.click("button").await?;
+        assert_eq!(program.model().await?, 2);
+        assert_eq!(program.html().await?.to_string(), "<button>2</button>");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn application() -> Result<()> {
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let shop = elm_root.prepare_application::<()>("Shop").await?;
+        let program = shop.init("http://localhost/", ()).await?;
+        assert_eq!(program.html().await?.find("body > h1")?.text(), "/");
+        program.click("a.product").await?;
+        assert_eq!(
+            program.take_navigations().await?,
+            [Navigation::PushUrl {
+                url: "http://localhost/products/7".to_owned()
+            }]
+        );
+        assert_eq!(program.url().await?, "http://localhost/products/7");
+        assert_eq!(program.html().await?.find("h1")?.text(), "/products/7");
+        program.click("a.help").await?;
+        assert_eq!(
+            program.take_navigations().await?,
+            [Navigation::Load {
+                url: "https://example.org/help".to_owned()
+            }]
+        );
+        program.change_url("/cart").await?;
+        assert_eq!(program.html().await?.find("h1")?.text(), "/cart");
+        program.back().await?;
+        assert_eq!(program.html().await?.find("h1")?.text(), "/products/7");
+        assert!(program.take_navigations().await?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn program() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Counter")]
+        enum Msg {
+            Increment,
+            Add(i32),
+            Save,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Model {
+            count: i32,
+            history: Vec<i32>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let counter = elm_root
+            .prepare_program::<i32, Msg, Model>("Counter")
+            .await?;
+        let program = counter.init(5).await?;
+        program.send(Msg::Increment).await?;
+        program.send(Msg::Add(3)).await?;
+        program.send(Msg::Save).await?;
+        assert_eq!(
+            program.model().await?,
+            Model {
+                count: 9,
+                history: vec![9]
+            }
+        );
+        assert_eq!(
+            program.take_port_messages().await?,
+            [elm_rust_binding::PortMessage {
+                port: "saved".to_owned(),
+                value: serde_json::json!(9)
+            }]
+        );
+        assert!(program.take_port_messages().await?.is_empty());
+        program.dispose().await?;
+        // Instances do not share their model
+        let other = counter.init(0).await?;
+        assert_eq!(other.model().await?.count, 0);
+        other.dispose().await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn update() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType)]
+        #[elm(module = "Counter")]
+        enum Msg {
+            Increment,
+            Add(i32),
+            Save,
+        }
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Model {
+            count: i32,
+            history: Vec<i32>,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_update = elm_root.prepare_update("Counter.update").await?;
+        let initial = Model {
+            count: 0,
+            history: vec![],
+        };
+        let models: Vec<Model> = elm_update
+            .call((initial, vec![Msg::Add(2), Msg::Save, Msg::Increment]))
+            .await?;
+        assert_eq!(
+            models,
+            [
+                Model {
+                    count: 2,
+                    history: vec![]
+                },
+                Model {
+                    count: 2,
+                    history: vec![2]
+                },
+                Model {
+                    count: 3,
+                    history: vec![2]
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "derive")]
+    #[tokio::test]
+    async fn derived_custom_type() -> Result<()> {
+        #[derive(Serialize, Deserialize, elm_rust_binding::ElmType, PartialEq, Debug)]
+        #[elm(module = "Test")]
+        enum Shape {
+            Circle(f64),
+            Rectangle { width: f64, height: f64 },
+            Empty,
+        }
+
+        let elm_root = ElmRoot::new("./tests/elm/src")?;
+        let elm_scale = elm_root.prepare("Test.scale").await?;
+        let result: Shape = elm_scale
+            .call(Shape::Rectangle {
+                width: 1.5,
+                height: 2.0,
+            })
+            .await?;
+        assert_eq!(
+            result,
+            Shape::Rectangle {
+                width: 3.0,
+                height: 4.0
+            }
         );
+        let result: Shape = elm_scale.call(Shape::Empty).await?;
+        assert_eq!(result, Shape::Empty);
         Ok(())
     }
 }